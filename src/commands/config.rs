@@ -0,0 +1,68 @@
+use crate::utils::config::{load_project_config_for, ConfigError};
+use crate::utils::output::Output;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Show the project configuration.",
+    long_about = "Shows settings read from vt-utils.toml merged with built-in defaults"
+)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    Show(ShowArgs),
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Print effective settings as TOML.",
+    long_about = "Prints export defaults, rule levels and lint thresholds used for a file or directory, with overrides matching it applied. Values not set in vt-utils.toml show the built-in default"
+)]
+struct ShowArgs {
+    /// File or directory the settings are resolved for, vt-utils.toml is
+    /// looked up from it upwards
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Config file to use instead of vt-utils.toml found from the path
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ConfigCommandError {
+    Config(ConfigError),
+    Serialize,
+}
+
+impl From<ConfigError> for ConfigCommandError {
+    fn from(e: ConfigError) -> Self {
+        ConfigCommandError::Config(e)
+    }
+}
+
+fn show(args: ShowArgs, output: &mut Output) -> Result<(), ConfigCommandError> {
+    let config = load_project_config_for(&args.config, &args.path)?;
+    let effective = config.effective_for(&args.path);
+
+    output.set_result("config_file", &config.path);
+    output.set_result("config", &effective);
+    match &config.path {
+        Some(path) => output.message(&format!("# {}", path.to_string_lossy())),
+        None => output.message("# No vt-utils.toml found, built-in defaults"),
+    }
+    let text = toml::to_string(&effective).map_err(|_| ConfigCommandError::Serialize)?;
+    output.print(&text);
+    Ok(())
+}
+
+pub fn run(args: ConfigArgs, output: &mut Output) -> Result<(), ConfigCommandError> {
+    match args.command {
+        ConfigCommands::Show(args) => show(args, output),
+    }
+}
@@ -0,0 +1,95 @@
+use crate::utils::dedupe::find_duplicate_materials;
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::output::Output;
+use crate::utils::report::FileReport;
+use crate::utils::variants::format_variant_path;
+use clap::Parser;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Find duplicated materials in .meta files.",
+    long_about = "Lists groups of identical materials in the base palette and reports which of them can be merged without changing any variant"
+)]
+pub struct DedupeArgs {
+    input_file: PathBuf,
+
+    /// Maximum difference allowed on any color channel or material property
+    #[arg(short, long, default_value_t = 0)]
+    tolerance: u8,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum DedupeError {
+    FileRead,
+    JsonParse(String),
+}
+
+impl From<std::io::Error> for DedupeError {
+    fn from(_e: std::io::Error) -> Self {
+        DedupeError::FileRead
+    }
+}
+
+fn format_indices(indices: &[usize]) -> String {
+    indices
+        .iter()
+        .map(|index| index.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+pub fn run(args: DedupeArgs, output: &mut Output) -> Result<(), DedupeError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| DedupeError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    let groups = find_duplicate_materials(&meta, args.tolerance);
+    output.set_result(
+        "groups",
+        groups
+            .iter()
+            .map(|group| {
+                json!({
+                    "materials": group.indices,
+                    "mergeable": group.is_mergeable(),
+                    "diverging_variants": group.diverging_variants,
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+    if groups.is_empty() {
+        output.message("No duplicated materials found");
+        return Ok(());
+    }
+
+    for group in groups.iter() {
+        if group.is_mergeable() {
+            output.message(&format!(
+                "Materials {} can be merged",
+                format_indices(&group.indices)
+            ));
+        } else {
+            output.message(&format!(
+                "Materials {} are duplicated in base palette but differ in variants:",
+                format_indices(&group.indices)
+            ));
+            for variant_path in group.diverging_variants.iter() {
+                output.message(&format!("  {}", format_variant_path(variant_path)));
+            }
+        }
+    }
+
+    Ok(())
+}
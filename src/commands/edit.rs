@@ -0,0 +1,543 @@
+use crate::utils::editor::{get_property_number, EditValueError, PaletteEditor};
+use crate::utils::get_colors_from_meta::{
+    get_colors_from_palette, MaterialProperty, MATERIAL_PROPERTIES,
+};
+use crate::utils::json_parse::VTMetaSchema;
+use crate::utils::json_patch::patch_meta_content;
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::output::Output;
+use crate::utils::provenance::{format_property_value, get_material_provenance};
+use crate::utils::report::FileReport;
+use crate::utils::save_image::get_palette_grid;
+use crate::utils::terminal_image::is_light_color;
+use crate::utils::variants::{
+    find_variant_path, format_variant_path, suggest_variant_paths, VariantLookupError, VariantPath,
+};
+use crate::utils::write_meta::serialize_meta_json;
+use clap::Parser;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+/// Width of the value slider in the properties panel
+const SLIDER_WIDTH: usize = 16;
+
+#[derive(Parser)]
+#[command(
+    about = "Edit the palette of a .meta file in the terminal.",
+    long_about = "Opens an interactive editor with the palette grid and the variant tree. Material properties are changed with sliders or typed values, with undo and redo. Saving keeps the formatting of the file"
+)]
+pub struct EditArgs {
+    input_file: PathBuf,
+
+    /// Variant selected on start, name or path like foo/bar/deep
+    #[arg(long)]
+    variant: Option<String>,
+
+    /// Match variant names regardless of letter case
+    #[arg(long, default_value_t = false)]
+    ignore_case: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum EditError {
+    FileRead,
+    JsonParse(String),
+    InvalidVariantName((String, Vec<VariantPath>)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    Terminal,
+    EmptyPalette,
+}
+
+impl From<std::io::Error> for EditError {
+    fn from(_e: std::io::Error) -> Self {
+        EditError::FileRead
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Variants,
+    Palette,
+    Properties,
+}
+
+struct App {
+    editor: PaletteEditor,
+    input_file: PathBuf,
+    /// File content and parsed data as saved on disk, base for patching
+    content: String,
+    saved_meta: VTMetaSchema,
+    focus: Focus,
+    property: usize,
+    preview: usize,
+    input: Option<String>,
+    status: String,
+    quit_pending: bool,
+    saved: bool,
+}
+
+fn property_label(property: &MaterialProperty) -> &'static str {
+    match property {
+        MaterialProperty::Color => "Color",
+        MaterialProperty::CompanyTint => "Company tint",
+        MaterialProperty::Emission => "Emission",
+        MaterialProperty::Glassiness => "Glassiness",
+        MaterialProperty::Smoothness => "Smoothness",
+        MaterialProperty::Specular => "Specular",
+    }
+}
+
+fn slider(value: u8) -> String {
+    let filled = value as usize * SLIDER_WIDTH / u8::MAX as usize;
+    format!(
+        "{}{}",
+        "█".repeat(filled),
+        "░".repeat(SLIDER_WIDTH - filled)
+    )
+}
+
+fn panel(title: String, focused: bool) -> Block<'static> {
+    let border_style = if focused {
+        Style::new().fg(Color::Yellow)
+    } else {
+        Style::new()
+    };
+    Block::bordered().title(title).border_style(border_style)
+}
+
+impl App {
+    fn selected_property(&self) -> MaterialProperty {
+        MATERIAL_PROPERTIES[self.property]
+    }
+
+    fn preview_property(&self) -> MaterialProperty {
+        MATERIAL_PROPERTIES[self.preview]
+    }
+
+    fn save(&mut self) {
+        let (content, preserved) =
+            match patch_meta_content(&self.content, &self.saved_meta, &self.editor.meta) {
+                Some(content) => (content, true),
+                None => match serialize_meta_json(&self.editor.meta) {
+                    Ok(content) => (content, false),
+                    Err(_) => {
+                        self.status = "Failed to serialize the file".to_string();
+                        return;
+                    }
+                },
+            };
+        if fs::write(&self.input_file, &content).is_err() {
+            self.status = "Failed to write the file".to_string();
+            return;
+        }
+        self.content = content;
+        self.saved_meta = self.editor.meta.clone();
+        self.editor.mark_saved();
+        self.saved = true;
+        self.status = if preserved {
+            "Saved".to_string()
+        } else {
+            "Saved, the file was reformatted".to_string()
+        };
+    }
+
+    fn move_in_palette(&mut self, rows: isize, columns: isize) {
+        let Ok(grid) = get_palette_grid(self.editor.meta.materials.len()) else {
+            let last = self.editor.meta.materials.len().saturating_sub(1) as isize;
+            let index = self.editor.selected_material as isize + rows + columns;
+            self.editor.selected_material = index.clamp(0, last) as usize;
+            return;
+        };
+        for (row, indexes) in grid.iter().enumerate() {
+            if let Some(column) = indexes
+                .iter()
+                .position(|index| *index == self.editor.selected_material)
+            {
+                let row = (row as isize + rows).clamp(0, grid.len() as isize - 1) as usize;
+                let column =
+                    (column as isize + columns).clamp(0, grid[row].len() as isize - 1) as usize;
+                self.editor.selected_material = grid[row][column];
+                return;
+            }
+        }
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.input.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                let value = input.clone();
+                let property = self.selected_property();
+                self.status = match self.editor.set_value(&property, &value) {
+                    Ok(()) => String::new(),
+                    Err(EditValueError::InvalidColor) => {
+                        format!("{} is not a hex color like ff8800", value)
+                    }
+                    Err(EditValueError::InvalidNumber) => {
+                        format!("{} is not a number between 0 and 255", value)
+                    }
+                };
+                self.input = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns false when the editor should close.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.input.is_some() {
+            self.handle_input_key(key);
+            return true;
+        }
+
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let step = if key.modifiers.contains(KeyModifiers::SHIFT) {
+            10
+        } else {
+            1
+        };
+        let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+            || (control && key.code == KeyCode::Char('c'));
+        if !quit {
+            self.quit_pending = false;
+        }
+        match key.code {
+            _ if quit => {
+                if !self.editor.has_changes() || self.quit_pending {
+                    return false;
+                }
+                self.quit_pending = true;
+                self.status = "Unsaved changes, press q again to quit or s to save".to_string();
+            }
+            KeyCode::Char('s') => self.save(),
+            KeyCode::Char('u') | KeyCode::Char('z') => {
+                if !self.editor.undo() {
+                    self.status = "Nothing to undo".to_string();
+                }
+            }
+            KeyCode::Char('r') | KeyCode::Char('y') => {
+                if !self.editor.redo() {
+                    self.status = "Nothing to redo".to_string();
+                }
+            }
+            KeyCode::Char('p') => self.preview = (self.preview + 1) % MATERIAL_PROPERTIES.len(),
+            KeyCode::Char('P') => {
+                self.preview =
+                    (self.preview + MATERIAL_PROPERTIES.len() - 1) % MATERIAL_PROPERTIES.len()
+            }
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Variants => Focus::Palette,
+                    Focus::Palette => Focus::Properties,
+                    Focus::Properties => Focus::Variants,
+                }
+            }
+            KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Variants => Focus::Properties,
+                    Focus::Palette => Focus::Variants,
+                    Focus::Properties => Focus::Palette,
+                }
+            }
+            code => match self.focus {
+                Focus::Variants => match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.editor.selected_variant =
+                            self.editor.selected_variant.saturating_sub(1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.editor.selected_variant =
+                            (self.editor.selected_variant + 1).min(self.editor.variants.len() - 1)
+                    }
+                    KeyCode::Enter => self.focus = Focus::Palette,
+                    _ => {}
+                },
+                Focus::Palette => match code {
+                    KeyCode::Up | KeyCode::Char('k') => self.move_in_palette(-1, 0),
+                    KeyCode::Down | KeyCode::Char('j') => self.move_in_palette(1, 0),
+                    KeyCode::Left | KeyCode::Char('h') => self.move_in_palette(0, -1),
+                    KeyCode::Right | KeyCode::Char('l') => self.move_in_palette(0, 1),
+                    KeyCode::Enter => self.focus = Focus::Properties,
+                    _ => {}
+                },
+                Focus::Properties => match code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.property = self.property.saturating_sub(1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.property = (self.property + 1).min(MATERIAL_PROPERTIES.len() - 1)
+                    }
+                    KeyCode::Left | KeyCode::Char('h') => {
+                        self.editor.adjust(&self.selected_property(), -step)
+                    }
+                    KeyCode::Right | KeyCode::Char('l') => {
+                        self.editor.adjust(&self.selected_property(), step)
+                    }
+                    KeyCode::Enter => {
+                        if let Some(material) = self.editor.material() {
+                            self.input =
+                                Some(format_property_value(&material, &self.selected_property()));
+                        }
+                    }
+                    _ => {}
+                },
+            },
+        }
+        true
+    }
+
+    fn render_variants(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<Line> = self
+            .editor
+            .variants
+            .iter()
+            .map(|entry| Line::from(format!("{}{}", "  ".repeat(entry.depth), entry.name)))
+            .collect();
+        let list = List::new(items)
+            .block(panel("Variants".to_string(), self.focus == Focus::Variants))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default().with_selected(Some(self.editor.selected_variant));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn render_palette(&self, frame: &mut Frame, area: Rect) {
+        let palette = self.editor.palette();
+        let colors = get_colors_from_palette(&palette, &self.preview_property());
+        let grid = get_palette_grid(colors.len())
+            .unwrap_or_else(|_| (0..colors.len()).map(|index| vec![index]).collect());
+
+        let mut lines: Vec<Line> = Vec::new();
+        for row in grid.iter() {
+            for line in 0..2 {
+                let spans: Vec<Span> = row
+                    .iter()
+                    .map(|index| {
+                        let [r, g, b] = colors[*index];
+                        let text_color = if is_light_color(&colors[*index]) {
+                            Color::Black
+                        } else {
+                            Color::White
+                        };
+                        let selected = *index == self.editor.selected_material;
+                        let text = match (line, selected) {
+                            (0, true) => format!("[{:>2}]{} ", index, self.override_mark(*index)),
+                            (0, false) => format!(" {:>2} {} ", index, self.override_mark(*index)),
+                            (_, true) => " ^^^  ".to_string(),
+                            _ => "      ".to_string(),
+                        };
+                        Span::styled(text, Style::new().bg(Color::Rgb(r, g, b)).fg(text_color))
+                    })
+                    .collect();
+                lines.push(Line::from(spans));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from("* overridden in this variant"));
+
+        let variant = match self.editor.variant_path().is_empty() {
+            true => "Materials".to_string(),
+            false => format_variant_path(self.editor.variant_path()),
+        };
+        let title = format!(
+            "{} - preview: {}",
+            variant,
+            property_label(&self.preview_property())
+        );
+        frame.render_widget(
+            Paragraph::new(lines).block(panel(title, self.focus == Focus::Palette)),
+            area,
+        );
+    }
+
+    fn override_mark(&self, index: usize) -> &'static str {
+        if self.editor.is_overridden(index) {
+            "*"
+        } else {
+            " "
+        }
+    }
+
+    fn render_properties(&self, frame: &mut Frame, area: Rect) {
+        let title = format!("Material {}", self.editor.selected_material);
+        let block = panel(title, self.focus == Focus::Properties);
+        let Some(material) = self.editor.material() else {
+            frame.render_widget(Paragraph::new("Not in the palette").block(block), area);
+            return;
+        };
+        let mut lines: Vec<Line> = Vec::new();
+        for (position, property) in MATERIAL_PROPERTIES.iter().enumerate() {
+            let value = match get_property_number(&material, property) {
+                Some(number) => format!("{:>3} {}", number, slider(number)),
+                None => material.color.to_string(),
+            };
+            let mut style = Style::new();
+            if position == self.property {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let mut spans = vec![Span::styled(
+                format!("{:<13}{}", property_label(property), value),
+                style,
+            )];
+            if *property == MaterialProperty::Color {
+                let [r, g, b] = get_colors_from_palette(&vec![material.clone()], property)[0];
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled("    ", Style::new().bg(Color::Rgb(r, g, b))));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.push(Line::from(""));
+        if let Ok(provenance) =
+            get_material_provenance(&self.editor.meta, self.editor.variant_path())
+        {
+            if let Some(entry) = provenance.get(self.editor.selected_material) {
+                lines.push(Line::from(format!(
+                    "Set by {}",
+                    entry.resolved().source.label()
+                )));
+            }
+        }
+        if let Some(input) = &self.input {
+            lines.push(Line::from(""));
+            lines.push(Line::from(format!(
+                "{}: {}_",
+                property_label(&self.selected_property()),
+                input
+            )));
+            lines.push(Line::from("Enter to apply, Esc to cancel"));
+        }
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [main, status, help] = Layout::vertical([
+            Constraint::Min(8),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [variants, palette, properties] = Layout::horizontal([
+            Constraint::Length(26),
+            Constraint::Min(30),
+            Constraint::Length(38),
+        ])
+        .areas(main);
+
+        self.render_variants(frame, variants);
+        self.render_palette(frame, palette);
+        self.render_properties(frame, properties);
+
+        let modified = if self.editor.has_changes() {
+            " [modified]"
+        } else {
+            ""
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{}{}  {}",
+                self.input_file.to_string_lossy(),
+                modified,
+                self.status
+            )),
+            status,
+        );
+        frame.render_widget(
+            Paragraph::new(
+                "Tab focus  arrows move  ←/→ adjust (Shift ×10)  Enter type value  p preview  u/r undo/redo  s save  q quit",
+            )
+            .style(Style::new().add_modifier(Modifier::DIM)),
+            help,
+        );
+    }
+}
+
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App) -> std::io::Result<()> {
+    loop {
+        terminal.draw(|frame| app.render(frame))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press && !app.handle_key(key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+pub fn run(args: EditArgs, output: &mut Output) -> Result<(), EditError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| EditError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    if meta.materials.is_empty() {
+        return Err(EditError::EmptyPalette);
+    }
+
+    let mut editor = PaletteEditor::new(meta.clone());
+    if let Some(variant) = args.variant {
+        let variant_path = match find_variant_path(&meta, &variant, args.ignore_case) {
+            Ok(variant_path) => variant_path,
+            Err(VariantLookupError::AmbiguousVariant(paths)) => {
+                return Err(EditError::AmbiguousVariant((variant, paths)));
+            }
+            Err(VariantLookupError::VariantNotExist) => {
+                let suggestions = suggest_variant_paths(&meta, &variant);
+                return Err(EditError::InvalidVariantName((variant, suggestions)));
+            }
+        };
+        editor.selected_variant = editor
+            .variants
+            .iter()
+            .position(|entry| entry.path == variant_path)
+            .unwrap_or(0);
+    }
+
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(EditError::Terminal);
+    }
+    let mut app = App {
+        editor,
+        input_file: args.input_file,
+        content,
+        saved_meta: meta,
+        focus: Focus::Palette,
+        property: 0,
+        preview: 0,
+        input: None,
+        status: String::new(),
+        quit_pending: false,
+        saved: false,
+    };
+    let mut terminal = ratatui::try_init().map_err(|_| EditError::Terminal)?;
+    let result = run_app(&mut terminal, &mut app);
+    ratatui::restore();
+    result.map_err(|_| EditError::Terminal)?;
+
+    if app.saved {
+        output.file_written(&app.input_file);
+    }
+    output.set_result("saved", app.saved);
+    Ok(())
+}
@@ -0,0 +1,172 @@
+use crate::utils::get_colors_from_meta::MaterialProperty;
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::output::Output;
+use crate::utils::palette::GetPaletteError;
+use crate::utils::provenance::{
+    format_property_value, get_material_provenance, MaterialOrigin, MaterialSource,
+};
+use crate::utils::report::FileReport;
+use crate::utils::variants::{
+    find_variant_path, format_variant_path, suggest_variant_paths, VariantLookupError, VariantPath,
+};
+use clap::Parser;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Explain where material values of a variant come from.",
+    long_about = "Lists every material index of a variant with the value it resolves to and the base Materials or ancestor variant which set it"
+)]
+pub struct ExplainArgs {
+    input_file: PathBuf,
+
+    /// Variant name, or path like foo/bar/deep when the name is not unique
+    variant: String,
+
+    #[arg(short, long, value_enum, default_value_t = MaterialProperty::Color)]
+    property: MaterialProperty,
+
+    /// Only explain these material indexes
+    #[arg(short, long)]
+    index: Vec<usize>,
+
+    /// Show every override of a material from the base palette to the variant
+    #[arg(short, long, default_value_t = false)]
+    chain: bool,
+
+    /// Match variant names regardless of letter case
+    #[arg(long, default_value_t = false)]
+    ignore_case: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum ExplainError {
+    FileRead,
+    JsonParse(String),
+    InvalidVariantName((String, Vec<VariantPath>)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    MaterialIndexOutOfRange(u8),
+    IndexNotInPalette((usize, usize)),
+}
+
+impl From<std::io::Error> for ExplainError {
+    fn from(_e: std::io::Error) -> Self {
+        ExplainError::FileRead
+    }
+}
+
+fn origin_json(origin: &MaterialOrigin, property: &MaterialProperty) -> Value {
+    let path = match &origin.source {
+        MaterialSource::Base => Value::Null,
+        MaterialSource::Variant(path) => json!(path),
+    };
+    json!({
+        "source": origin.source.label(),
+        "variant_path": path,
+        "value": format_property_value(&origin.material, property),
+    })
+}
+
+pub fn run(args: ExplainArgs, output: &mut Output) -> Result<(), ExplainError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| ExplainError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    let variant_path = match find_variant_path(&meta, &args.variant, args.ignore_case) {
+        Ok(variant_path) => variant_path,
+        Err(VariantLookupError::AmbiguousVariant(paths)) => {
+            return Err(ExplainError::AmbiguousVariant((args.variant, paths)));
+        }
+        Err(VariantLookupError::VariantNotExist) => {
+            let suggestions = suggest_variant_paths(&meta, &args.variant);
+            return Err(ExplainError::InvalidVariantName((
+                args.variant,
+                suggestions,
+            )));
+        }
+    };
+
+    let provenance = match get_material_provenance(&meta, &variant_path) {
+        Ok(provenance) => provenance,
+        Err(GetPaletteError::MaterialIndexOutOfRange(index)) => {
+            return Err(ExplainError::MaterialIndexOutOfRange(index));
+        }
+        Err(_) => {
+            return Err(ExplainError::InvalidVariantName((args.variant, Vec::new())));
+        }
+    };
+    if let Some(index) = args.index.iter().find(|index| **index >= provenance.len()) {
+        return Err(ExplainError::IndexNotInPalette((*index, provenance.len())));
+    }
+
+    let property = args.property;
+    let explained: Vec<_> = provenance
+        .iter()
+        .filter(|entry| args.index.is_empty() || args.index.contains(&entry.index))
+        .collect();
+
+    output.set_result("variant_path", &variant_path);
+    output.set_result("property", property.to_string());
+    output.set_result(
+        "materials",
+        explained
+            .iter()
+            .map(|entry| {
+                let resolved = entry.resolved();
+                json!({
+                    "index": entry.index,
+                    "value": format_property_value(&resolved.material, &property),
+                    "source": resolved.source.label(),
+                    "chain": entry
+                        .chain
+                        .iter()
+                        .map(|origin| origin_json(origin, &property))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    output.message(&format!(
+        "{} {} ({})",
+        args.input_file.to_string_lossy(),
+        property,
+        format_variant_path(&variant_path)
+    ));
+    for entry in explained {
+        let resolved = entry.resolved();
+        let mut line = format!(
+            "{:>3}  {:<6}  {}",
+            entry.index,
+            format_property_value(&resolved.material, &property),
+            resolved.source.label()
+        );
+        if args.chain && entry.chain.len() > 1 {
+            let chain: Vec<String> = entry
+                .chain
+                .iter()
+                .map(|origin| {
+                    format!(
+                        "{} ({})",
+                        format_property_value(&origin.material, &property),
+                        origin.source.label()
+                    )
+                })
+                .collect();
+            line.push_str(&format!("\n     {}", chain.join(" -> ")));
+        }
+        output.message(&line);
+    }
+    Ok(())
+}
@@ -1,11 +1,40 @@
+use crate::error::CliError;
+use crate::utils::config::{
+    load_project_config_for, ConfigError, ExportConfig, ExportImageFormat, ExportLayout,
+};
+use crate::utils::diagnostic::{Diagnostic, Severity};
+use crate::utils::export_manifest::{
+    manifest_path, read_manifest, write_manifest, ExportManifest, ManifestEntry, MANIFEST_FILE_NAME,
+};
 use crate::utils::get_colors_from_meta::{get_colors_from_palette, MaterialProperty};
-use crate::utils::json_parse::{parse_material_json, VTMetaReadError};
-use crate::utils::palette::get_palette_from_meta;
-use crate::utils::save_image::{save_image, SaveImageSuccess};
-use crate::utils::variants::get_variants_names_from_meta;
-use clap::Parser;
-use std::path::PathBuf;
-use std::{collections::HashSet, fs};
+use crate::utils::jobs::{map_ordered, run_with_jobs};
+use crate::utils::json_parse::VTMetaSchema;
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::lut::{
+    build_lut_image, get_lut_palettes, get_lut_rows, LutSidecar, LUT_SIDECAR_VERSION,
+};
+use crate::utils::meta_files::{find_meta_files, FindMetaFilesError, MetaFileFilter};
+use crate::utils::name_template::{NameTemplate, NameTemplateError, NameValues};
+use crate::utils::output::{hash_file, Output};
+use crate::utils::palette::{get_palette_from_variant_path, GetPaletteError, VTPalette};
+use crate::utils::report::FileReport;
+use crate::utils::save_image::{
+    get_palette_grid, save_image, save_rgb_image, Colors, SaveImageError, SaveImageSuccess,
+};
+use crate::utils::variant_select::{
+    is_variant_pattern, select_variants, SelectVariantsError, UNMATCHED_VARIANT,
+};
+use crate::utils::variants::{
+    format_variant_path, suggest_variant_paths, VariantPath, VARIANT_PATH_SEPARATOR,
+};
+use crate::utils::watch::{DirectoryWatcher, WATCH_DEBOUNCE};
+use crate::utils::write_meta::serialize_meta_json;
+use clap::{Parser, ValueEnum};
+use indexmap::{IndexMap, IndexSet};
+use rayon::prelude::*;
+use serde_json::{json, Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 #[derive(Hash, PartialEq, Eq, Debug)]
 enum ExportOperation {
@@ -15,25 +44,82 @@ enum ExportOperation {
 use ExportOperation::Export;
 use MaterialProperty::*;
 
-type ExportOperations = HashSet<ExportOperation>;
+type ExportOperations = IndexSet<ExportOperation>;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Layout {
+    /// Square palette texture like the game uses, one per property
+    Grid,
+    /// Lookup texture with the resolved palette of every variant in a row,
+    /// plus a JSON sidecar mapping rows to variant paths
+    Lut,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ImageFormat {
+    Png,
+    Tga,
+    Bmp,
+}
+
+impl ImageFormat {
+    /// Extension of the files, which also selects the encoder
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Tga => "tga",
+            ImageFormat::Bmp => "bmp",
+        }
+    }
+}
+
+impl From<ExportImageFormat> for ImageFormat {
+    fn from(format: ExportImageFormat) -> Self {
+        match format {
+            ExportImageFormat::Png => ImageFormat::Png,
+            ExportImageFormat::Tga => ImageFormat::Tga,
+            ExportImageFormat::Bmp => ImageFormat::Bmp,
+        }
+    }
+}
+
+impl From<ExportLayout> for Layout {
+    fn from(layout: ExportLayout) -> Self {
+        match layout {
+            ExportLayout::Grid => Layout::Grid,
+            ExportLayout::Lut => Layout::Lut,
+        }
+    }
+}
 
 #[derive(Parser)]
 #[command(
     about = "Export texture images from .meta files.",
-    long_about = "Creates material images from .meta files for usage in 3D editor software. Usefull for preview of exporting colored 3D models"
+    long_about = "Creates material images from .meta files for usage in 3D editor software. Usefull for preview of exporting colored 3D models. Defaults are read from the [export] table of vt-utils.toml found in the directory of the input or its parents, command line flags take precedence"
 )]
 pub struct ExportArgs {
+    /// .meta file, or directory searched recursively for .meta files
     input_file: PathBuf,
 
-    #[arg(default_value_t = String::from("."))]
-    output_directory: String,
+    /// Directory for the textures [default: .]
+    output_directory: Option<PathBuf>,
 
     #[arg(short, long)]
     filename: Option<String>,
 
-    #[arg(short, long, default_value_t = false)]
+    /// Name of exported files with {stem}, {model}, {property}, {prop}, {variant},
+    /// {variant_path}, {layout} and {ext}, / creates subdirectories
+    /// [default: {stem}-{property}-{variant}, {stem}-{property}-{layout} for lut]
+    #[arg(long, value_name = "TEMPLATE")]
+    name_template: Option<String>,
+
+    #[arg(short, long, default_value_t = false, overrides_with = "no_verbose")]
     verbose: bool,
 
+    /// Turn off verbose of vt-utils.toml
+    #[arg(long, default_value_t = false, overrides_with = "verbose")]
+    no_verbose: bool,
+
     #[arg(short, long, default_value_t = false)]
     color: bool,
 
@@ -52,14 +138,186 @@ pub struct ExportArgs {
     #[arg(short = 'r', long, default_value_t = false)]
     specular: bool,
 
+    /// Variant name or path like foo/bar/deep, glob like 'winter*', foo/** for a
+    /// variant with all its descendants or regex prefixed with re:. Globs and
+    /// regexes match names, or full paths when they contain /. Can be repeated
     #[arg(long)]
-    variant: Option<String>,
+    variant: Vec<String>,
+
+    /// Match variant names regardless of letter case
+    #[arg(long, default_value_t = false, overrides_with = "no_ignore_case")]
+    ignore_case: bool,
+
+    /// Turn off ignore-case of vt-utils.toml
+    #[arg(long, default_value_t = false, overrides_with = "ignore_case")]
+    no_ignore_case: bool,
 
     #[arg(short, long, default_value_t = false)]
     all: bool,
+
+    /// [default: grid]
+    #[arg(long, value_enum)]
+    layout: Option<Layout>,
+
+    /// File format of the textures [default: png]
+    #[arg(long, value_enum)]
+    image_format: Option<ImageFormat>,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false, overrides_with = "no_lenient")]
+    lenient: bool,
+
+    /// Turn off lenient of vt-utils.toml
+    #[arg(long, default_value_t = false, overrides_with = "lenient")]
+    no_lenient: bool,
+
+    /// Glob of files to export from a directory, relative to it [default: **/*.obj.meta]
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Glob of files to skip when exporting a directory
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Number of images and files processed in parallel, one per CPU core by default
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Export files even when the manifest says they are up to date
+    #[arg(long, default_value_t = false)]
+    force: bool,
+
+    /// Remove outputs of deleted variants and .meta files instead of only reporting them
+    #[arg(long, default_value_t = false, overrides_with = "no_prune")]
+    prune: bool,
+
+    /// Turn off prune of vt-utils.toml
+    #[arg(long, default_value_t = false, overrides_with = "prune")]
+    no_prune: bool,
+
+    /// Keep running and export .meta files again whenever they are saved
+    #[arg(short, long, default_value_t = false)]
+    watch: bool,
+
+    /// Config file to use instead of vt-utils.toml found from the input
+    #[arg(long)]
+    config: Option<PathBuf>,
+}
+
+/// State of one `export` run resolved from the arguments and the config.
+struct ExportContext<'a> {
+    args: &'a ExportArgs,
+    name_template: NameTemplate,
+    /// Directory manifest keys are relative to
+    input_root: PathBuf,
 }
 
-fn get_filename_from_path(path: &PathBuf) -> String {
+impl ExportContext<'_> {
+    /// Manifest key of `input_file`, its path relative to the input root.
+    /// Inputs outside of it are kept absolute.
+    fn manifest_key(&self, input_file: &Path) -> String {
+        let input_file = input_file
+            .canonicalize()
+            .unwrap_or_else(|_| input_file.to_path_buf());
+        manifest_path(
+            input_file
+                .strip_prefix(&self.input_root)
+                .unwrap_or(&input_file),
+        )
+    }
+}
+
+/// Value of a flag with a `--no-` counterpart, the config value when neither
+/// is given.
+fn config_flag(flag: bool, no_flag: bool, config: Option<bool>) -> bool {
+    match (flag, no_flag) {
+        (true, _) => true,
+        (_, true) => false,
+        _ => config.unwrap_or(false),
+    }
+}
+
+impl ExportArgs {
+    fn output_directory(&self) -> &Path {
+        self.output_directory.as_deref().unwrap_or(Path::new("."))
+    }
+
+    fn layout(&self) -> Layout {
+        self.layout.unwrap_or(Layout::Grid)
+    }
+
+    fn image_extension(&self) -> &'static str {
+        self.image_format.unwrap_or(ImageFormat::Png).extension()
+    }
+
+    fn name_template(&self) -> &str {
+        match (&self.name_template, self.layout()) {
+            (Some(name_template), _) => name_template,
+            (None, Layout::Grid) => "{stem}-{property}-{variant}",
+            (None, Layout::Lut) => "{stem}-{property}-{layout}",
+        }
+    }
+
+    /// Fills options not given on the command line from `[export]` of the
+    /// config file. Properties of the config are used only when no property
+    /// flag is given.
+    fn apply_config(&mut self, config: &ExportConfig) {
+        let has_properties = self.color
+            || self.company_tint
+            || self.emission
+            || self.glassiness
+            || self.smoothness
+            || self.specular
+            || self.all;
+        if !has_properties {
+            for property in config.properties.iter().flatten() {
+                match property {
+                    Color => self.color = true,
+                    CompanyTint => self.company_tint = true,
+                    Emission => self.emission = true,
+                    Glassiness => self.glassiness = true,
+                    Smoothness => self.smoothness = true,
+                    Specular => self.specular = true,
+                }
+            }
+            self.all = config.all.unwrap_or(false);
+        }
+
+        if self.output_directory.is_none() {
+            self.output_directory = config.output_directory.clone();
+        }
+        if self.variant.is_empty() {
+            self.variant = config.variant.clone().unwrap_or_default();
+        }
+        if self.filename.is_none() {
+            self.filename = config.filename.clone();
+        }
+        if self.name_template.is_none() {
+            self.name_template = config.name_template.clone();
+        }
+        if self.layout.is_none() {
+            self.layout = config.layout.map(Layout::from);
+        }
+        if self.image_format.is_none() {
+            self.image_format = config.image_format.map(ImageFormat::from);
+        }
+        if self.include.is_empty() {
+            self.include = config.include.clone().unwrap_or_default();
+        }
+        if self.exclude.is_empty() {
+            self.exclude = config.exclude.clone().unwrap_or_default();
+        }
+        if self.jobs.is_none() {
+            self.jobs = config.jobs;
+        }
+        self.ignore_case = config_flag(self.ignore_case, self.no_ignore_case, config.ignore_case);
+        self.lenient = config_flag(self.lenient, self.no_lenient, config.lenient);
+        self.verbose = config_flag(self.verbose, self.no_verbose, config.verbose);
+        self.prune = config_flag(self.prune, self.no_prune, config.prune);
+    }
+}
+
+fn get_filename_from_path(path: &Path) -> String {
     if let Some(stem) = path.file_stem() {
         stem.to_string_lossy().to_string()
     } else {
@@ -70,9 +328,30 @@ fn get_filename_from_path(path: &PathBuf) -> String {
 #[derive(Debug)]
 pub enum ExportError {
     FileRead,
-    JsonParse,
+    JsonParse(String),
     NoOperations,
-    InvalidVariantName((String, Option<Vec<String>>)),
+    /// Every `--variant` pattern matched nothing, with suggestions for names
+    UnmatchedVariants(Vec<(String, Vec<VariantPath>)>),
+    InvalidVariantPattern((String, String)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    MaterialIndexOutOfRange(u8),
+    InvalidPaletteSize(usize),
+    VariantWithLut,
+    ImageWrite(Vec<PathBuf>),
+    FilenameWithDirectory,
+    InvalidNameTemplate((String, NameTemplateError)),
+    /// Name given to more than one file by the name template
+    DuplicateOutputName(String),
+    InvalidGlob((String, String)),
+    DirectoryRead(PathBuf),
+    NoMetaFiles(PathBuf),
+    /// Failed files of a directory with their exit codes, and the number of
+    /// files found
+    FilesFailed((Vec<(PathBuf, u8)>, usize)),
+    Config(ConfigError),
+    WatchWithJson,
+    /// Watched path and the reason it can't be watched
+    Watch((PathBuf, String)),
 }
 
 impl From<std::io::Error> for ExportError {
@@ -81,9 +360,31 @@ impl From<std::io::Error> for ExportError {
     }
 }
 
-impl From<VTMetaReadError> for ExportError {
-    fn from(_e: VTMetaReadError) -> Self {
-        ExportError::JsonParse
+impl From<ConfigError> for ExportError {
+    fn from(e: ConfigError) -> Self {
+        ExportError::Config(e)
+    }
+}
+
+impl From<FindMetaFilesError> for ExportError {
+    fn from(e: FindMetaFilesError) -> Self {
+        match e {
+            FindMetaFilesError::InvalidGlob(glob) => ExportError::InvalidGlob(glob),
+            FindMetaFilesError::DirectoryRead(path) => ExportError::DirectoryRead(path),
+        }
+    }
+}
+
+impl From<SelectVariantsError> for ExportError {
+    fn from(e: SelectVariantsError) -> Self {
+        match e {
+            SelectVariantsError::InvalidPattern(pattern) => {
+                ExportError::InvalidVariantPattern(pattern)
+            }
+            SelectVariantsError::AmbiguousVariant(variant) => {
+                ExportError::AmbiguousVariant(variant)
+            }
+        }
     }
 }
 
@@ -98,7 +399,7 @@ struct ProcessArgs {
 }
 
 fn process_operations(args: &ProcessArgs) -> Result<ExportOperations, ExportError> {
-    let mut operations: ExportOperations = HashSet::new();
+    let mut operations: ExportOperations = IndexSet::new();
     if args.color {
         operations.insert(Export(Color));
     }
@@ -126,33 +427,685 @@ fn process_operations(args: &ProcessArgs) -> Result<ExportOperations, ExportErro
         operations.insert(Export(Specular));
     }
 
-    if operations.len() == 0 {
+    if operations.is_empty() {
         return Err(ExportError::NoOperations);
     }
     Ok(operations)
 }
 
-pub fn run(args: ExportArgs) -> Result<(), ExportError> {
-    let filename = if let Some(input_filename) = args.filename {
-        input_filename
-    } else {
-        get_filename_from_path(&args.input_file)
+fn get_properties(operations: &ExportOperations) -> Vec<&MaterialProperty> {
+    operations
+        .iter()
+        .map(|operation| {
+            let ExportOperation::Export(material_type) = operation;
+            material_type
+        })
+        .collect()
+}
+
+/// Variants are taken from the file, so only material indexes can be wrong.
+fn palette_error(e: GetPaletteError, variant_path: &VariantPath) -> ExportError {
+    match e {
+        GetPaletteError::MaterialIndexOutOfRange(index) => {
+            ExportError::MaterialIndexOutOfRange(index)
+        }
+        _ => ExportError::UnmatchedVariants(vec![(format_variant_path(variant_path), Vec::new())]),
+    }
+}
+
+fn get_export_palette(
+    meta: &VTMetaSchema,
+    variant_path: &Option<VariantPath>,
+) -> Result<VTPalette, ExportError> {
+    match variant_path {
+        Some(variant_path) => get_palette_from_variant_path(meta, variant_path)
+            .map_err(|e| palette_error(e, variant_path)),
+        None => Ok(meta.materials.clone()),
+    }
+}
+
+/// Variants selected by `--variant` with the names used in their file names,
+/// the base palette with an empty name when no variant is given. Patterns matching
+/// nothing are warnings unless none of them matched.
+fn get_export_variants(
+    meta: &VTMetaSchema,
+    args: &ExportArgs,
+    input_file: &Path,
+    content: &str,
+    output: &mut Output,
+) -> Result<Vec<(Option<VariantPath>, String)>, ExportError> {
+    if args.variant.is_empty() {
+        return Ok(vec![(None, "".to_string())]);
+    }
+
+    let selection = select_variants(meta, &args.variant, args.ignore_case)?;
+    if selection.variants.is_empty() {
+        let unmatched = selection
+            .unmatched
+            .into_iter()
+            .map(|pattern| {
+                let suggestions = match is_variant_pattern(&pattern) {
+                    true => Vec::new(),
+                    false => suggest_variant_paths(meta, &pattern),
+                };
+                (pattern, suggestions)
+            })
+            .collect();
+        return Err(ExportError::UnmatchedVariants(unmatched));
+    }
+
+    let warnings: Vec<Diagnostic> = selection
+        .unmatched
+        .iter()
+        .map(|pattern| Diagnostic {
+            rule: UNMATCHED_VARIANT,
+            severity: Severity::Warning,
+            path: vec!["Variants".to_string()],
+            message: format!("No variant matches {}", pattern),
+        })
+        .collect();
+    output.report_repairs(&FileReport::from_diagnostics(
+        input_file, content, &warnings,
+    ));
+
+    Ok(selection
+        .variants
+        .into_iter()
+        .map(|variant| {
+            let name = variant.name.replace(VARIANT_PATH_SEPARATOR, "-");
+            (Some(variant.path), name)
+        })
+        .collect())
+}
+
+fn save_output_image(
+    image: Result<SaveImageSuccess, SaveImageError>,
+    verbose: bool,
+    output_path: &Path,
+    failed_files: &mut Vec<PathBuf>,
+    output: &mut Output,
+) -> bool {
+    match image {
+        Ok(SaveImageSuccess::SaveOk(message)) => {
+            if verbose {
+                output.message(&message);
+            }
+            output.file_written(output_path);
+            true
+        }
+        Err(_) => {
+            failed_files.push(output_path.to_path_buf());
+            false
+        }
+    }
+}
+
+/// Writes one lookup texture per property and the sidecar describing rows.
+fn export_lut(
+    meta: &VTMetaSchema,
+    context: &ExportContext,
+    output_directory: &Path,
+    filename: &str,
+    operations: &ExportOperations,
+    result: &mut Map<String, Value>,
+    output: &mut Output,
+) -> Result<Vec<PathBuf>, ExportError> {
+    let args = context.args;
+    get_palette_grid(meta.materials.len())
+        .map_err(|_| ExportError::InvalidPaletteSize(meta.materials.len()))?;
+    let palettes = get_lut_palettes(meta).map_err(|e| palette_error(e, &Vec::new()))?;
+
+    let properties = get_properties(operations);
+    let lut_name = |property: Option<MaterialProperty>, ext: &str| {
+        context.name_template.render(&NameValues {
+            stem: filename,
+            property,
+            layout: "lut",
+            ext,
+            ..NameValues::default()
+        })
     };
+    let texture_filenames: Vec<String> = properties
+        .iter()
+        .map(|material_type| lut_name(Some(**material_type), args.image_extension()))
+        .collect();
+    let sidecar_filename = lut_name(None, "json");
+    check_output_names(texture_filenames.iter().chain([&sidecar_filename]))?;
 
-    let content = fs::read_to_string(&args.input_file)?;
+    let images: Vec<_> = properties
+        .par_iter()
+        .zip(texture_filenames)
+        .map(|(material_type, texture_filename)| {
+            let rows: Vec<Colors> = palettes
+                .iter()
+                .map(|(_, palette)| get_colors_from_palette(palette, material_type))
+                .collect();
+            let mut output_path = output_directory.to_path_buf();
+            let image =
+                save_rgb_image(&build_lut_image(&rows), &mut output_path, &texture_filename);
+            (output_path, texture_filename, image)
+        })
+        .collect();
 
-    let meta = parse_material_json(&content)?;
+    let mut failed_files: Vec<PathBuf> = Vec::new();
+    let mut written_files: Vec<PathBuf> = Vec::new();
+    let mut operation_results = Vec::new();
+    let mut textures: IndexMap<String, String> = IndexMap::new();
+    for (material_type, (output_path, texture_filename, image)) in properties.iter().zip(images) {
+        let written =
+            save_output_image(image, args.verbose, &output_path, &mut failed_files, output);
+        if written {
+            written_files.push(output_path.clone());
+        }
+        textures.insert(material_type.to_string(), texture_filename);
+        operation_results.push(json!({
+            "property": material_type.to_string(),
+            "file": output_path.to_string_lossy(),
+            "written": written,
+        }));
+    }
 
-    let palette = if let Ok(palette) = get_palette_from_meta(&meta, &args.variant) {
-        palette
-    } else {
-        let variant_name = args.variant.unwrap_or("".to_string());
-        let available_variants = get_variants_names_from_meta(&meta);
-        return Err(ExportError::InvalidVariantName((
-            variant_name,
-            available_variants,
-        )));
+    let sidecar = LutSidecar {
+        version: LUT_SIDECAR_VERSION,
+        width: meta.materials.len(),
+        height: palettes.len(),
+        textures,
+        rows: get_lut_rows(&palettes),
     };
+    let sidecar_path = output_directory.join(sidecar_filename);
+    let written = serialize_meta_json(&sidecar).map(|content| {
+        if let Some(parent) = sidecar_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&sidecar_path, content)
+    });
+    match written {
+        Ok(Ok(())) => {
+            if args.verbose {
+                output.message(&format!(
+                    "Succesfully saved {}",
+                    sidecar_path.to_string_lossy()
+                ));
+            }
+            output.file_written(&sidecar_path);
+            written_files.push(sidecar_path.clone());
+        }
+        _ => failed_files.push(sidecar_path.clone()),
+    }
+    result.insert("operations".to_string(), json!(operation_results));
+    result.insert("sidecar".to_string(), json!(sidecar_path.to_string_lossy()));
+
+    if !failed_files.is_empty() {
+        return Err(ExportError::ImageWrite(failed_files));
+    }
+    Ok(written_files)
+}
+
+/// Exports one .meta file into `output_directory` and returns the written
+/// files. The JSON result of the file is kept in `result` also when the export
+/// fails.
+fn export_file(
+    context: &ExportContext,
+    input_file: &Path,
+    output_directory: &Path,
+    operations: &ExportOperations,
+    result: &mut Map<String, Value>,
+    output: &mut Output,
+) -> Result<Vec<PathBuf>, ExportError> {
+    let args = context.args;
+    let filename = match &args.filename {
+        Some(filename) => filename.to_owned(),
+        None => get_filename_from_path(input_file),
+    };
+
+    let content = fs::read_to_string(input_file)?;
+
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| ExportError::JsonParse(e.render(input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        input_file, &content, &repairs,
+    ));
+
+    if args.layout() == Layout::Lut {
+        return export_lut(
+            &meta,
+            context,
+            output_directory,
+            &filename,
+            operations,
+            result,
+            output,
+        );
+    }
+
+    let variants = get_export_variants(&meta, args, input_file, &content, output)?;
+    let palettes = variants
+        .iter()
+        .map(|(variant_path, _)| get_export_palette(&meta, variant_path))
+        .collect::<Result<Vec<VTPalette>, ExportError>>()?;
+    get_palette_grid(meta.materials.len())
+        .map_err(|_| ExportError::InvalidPaletteSize(meta.materials.len()))?;
+
+    // Images are encoded in parallel and reported in variant and property order
+    let properties = get_properties(operations);
+    let images: Vec<(usize, &MaterialProperty)> = (0..variants.len())
+        .flat_map(|variant| properties.iter().map(move |property| (variant, *property)))
+        .collect();
+    let image_filenames: Vec<String> = images
+        .iter()
+        .map(|(variant, material_type)| {
+            let (variant_path, variant_name) = &variants[*variant];
+            context.name_template.render(&NameValues {
+                stem: &filename,
+                property: Some(**material_type),
+                variant: variant_name,
+                variant_path: &variant_path
+                    .as_ref()
+                    .map(format_variant_path)
+                    .unwrap_or_default(),
+                layout: "grid",
+                ext: args.image_extension(),
+            })
+        })
+        .collect();
+    check_output_names(image_filenames.iter())?;
+
+    let saved: Vec<_> = images
+        .par_iter()
+        .zip(image_filenames)
+        .map(|((variant, material_type), full_filename)| {
+            let colors = get_colors_from_palette(&palettes[*variant], material_type);
+            let mut output_path = output_directory.to_path_buf();
+            let image = save_image(&colors, &mut output_path, &full_filename);
+            (output_path, image)
+        })
+        .collect();
+
+    let mut failed_images: Vec<PathBuf> = Vec::new();
+    let mut written_files: Vec<PathBuf> = Vec::new();
+    let mut operation_results: Vec<Vec<Value>> = vec![Vec::new(); variants.len()];
+    for ((variant, material_type), (output_path, image)) in images.into_iter().zip(saved) {
+        let written = save_output_image(
+            image,
+            args.verbose,
+            &output_path,
+            &mut failed_images,
+            output,
+        );
+        if written {
+            written_files.push(output_path.clone());
+        }
+        operation_results[variant].push(json!({
+            "property": material_type.to_string(),
+            "file": output_path.to_string_lossy(),
+            "written": written,
+        }));
+    }
+    let export_results: Vec<Value> = variants
+        .iter()
+        .zip(operation_results)
+        .map(|((variant_path, _), operations)| {
+            json!({
+                "variant_path": variant_path,
+                "operations": operations,
+            })
+        })
+        .collect();
+    result.insert("exports".to_string(), json!(export_results));
+
+    if !failed_images.is_empty() {
+        return Err(ExportError::ImageWrite(failed_images));
+    }
+    Ok(written_files)
+}
+
+/// Fails when the name template gives two files of a model the same name.
+fn check_output_names<'a>(names: impl Iterator<Item = &'a String>) -> Result<(), ExportError> {
+    let mut seen: IndexSet<&String> = IndexSet::new();
+    for name in names {
+        if !seen.insert(name) {
+            return Err(ExportError::DuplicateOutputName(name.to_owned()));
+        }
+    }
+    Ok(())
+}
+
+/// Export options recorded in the manifest, a change of any of them exports
+/// the file again.
+fn get_manifest_options(args: &ExportArgs, operations: &ExportOperations) -> Value {
+    let properties: Vec<String> = get_properties(operations)
+        .iter()
+        .map(|material_type| material_type.to_string())
+        .collect();
+    let layout = match args.layout() {
+        Layout::Grid => "grid",
+        Layout::Lut => "lut",
+    };
+    json!({
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "properties": properties,
+        "layout": layout,
+        "image_format": args.image_extension(),
+        "variant": args.variant,
+        "ignore_case": args.ignore_case,
+        "filename": args.filename,
+        "name_template": args.name_template(),
+        "lenient": args.lenient,
+    })
+}
+
+/// Removes `stale` outputs with `--prune`, otherwise reports them. Returns the
+/// files which still exist.
+fn handle_stale_outputs(
+    args: &ExportArgs,
+    input_file: &Path,
+    root_directory: &Path,
+    stale: Vec<String>,
+    result: &mut Map<String, Value>,
+    output: &mut Output,
+) -> Vec<String> {
+    if stale.is_empty() {
+        return stale;
+    }
+    if !args.prune {
+        output.error_message(&format!(
+            "{}: {} stale output(s), use --prune to remove them:",
+            input_file.to_string_lossy(),
+            stale.len()
+        ));
+        for path in stale.iter() {
+            output.error_message(&format!(
+                "  {}",
+                root_directory.join(path).to_string_lossy()
+            ));
+        }
+        result.insert("stale".to_string(), json!(stale));
+        return stale;
+    }
+
+    let mut removed: Vec<String> = Vec::new();
+    let mut remaining: Vec<String> = Vec::new();
+    for path in stale {
+        let full_path = root_directory.join(&path);
+        match fs::remove_file(&full_path) {
+            Ok(()) => {
+                if args.verbose {
+                    output.message(&format!("Removed {}", full_path.to_string_lossy()));
+                }
+                removed.push(path);
+            }
+            Err(_) => {
+                output.error_message(&format!("Failed to remove {}", full_path.to_string_lossy()));
+                remaining.push(path);
+            }
+        }
+    }
+    result.insert("removed".to_string(), json!(removed));
+    result.insert("stale".to_string(), json!(remaining));
+    remaining
+}
+
+/// Exports one .meta file unless `previous` shows it is up to date. Returns
+/// the manifest entry of the file, outputs are relative to `root_directory`.
+#[allow(clippy::too_many_arguments)]
+fn export_incremental(
+    context: &ExportContext,
+    input_file: &Path,
+    output_directory: &Path,
+    root_directory: &Path,
+    operations: &ExportOperations,
+    options: &Value,
+    previous: Option<&ManifestEntry>,
+    result: &mut Map<String, Value>,
+    output: &mut Output,
+) -> Result<ManifestEntry, ExportError> {
+    let args = context.args;
+    let input_sha256 = hash_file(input_file).ok_or(ExportError::FileRead)?.sha256;
+
+    if let Some(previous) = previous.filter(|previous| {
+        !args.force && previous.is_up_to_date(&input_sha256, options, root_directory)
+    }) {
+        result.insert("skipped".to_string(), json!(true));
+        let mut entry = previous.clone();
+        let stale = previous
+            .stale
+            .iter()
+            .filter(|path| root_directory.join(path).is_file())
+            .cloned()
+            .collect();
+        entry.stale = handle_stale_outputs(args, input_file, root_directory, stale, result, output);
+        return Ok(entry);
+    }
+
+    result.insert("skipped".to_string(), json!(false));
+    let written_files = export_file(
+        context,
+        input_file,
+        output_directory,
+        operations,
+        result,
+        output,
+    )?;
+    let mut entry = ManifestEntry::new(
+        input_sha256,
+        options.clone(),
+        root_directory,
+        &written_files,
+    );
+    let stale = previous
+        .map(|previous| entry.stale_outputs(previous, root_directory))
+        .unwrap_or_default();
+    entry.stale = handle_stale_outputs(args, input_file, root_directory, stale, result, output);
+    Ok(entry)
+}
+
+/// Writes the manifest. An error of the export takes precedence over a failed
+/// manifest write.
+fn save_manifest(
+    root_directory: &Path,
+    manifest: &ExportManifest,
+    exported: Result<(), ExportError>,
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    match write_manifest(root_directory, manifest) {
+        Ok(path) => {
+            output.file_written(&path);
+            exported
+        }
+        Err(_) => exported.and(Err(ExportError::ImageWrite(vec![
+            root_directory.join(MANIFEST_FILE_NAME)
+        ]))),
+    }
+}
+
+/// Exports every .meta file found in the input directory into the same
+/// subdirectories of the output directory. A failed file is reported and the
+/// remaining files are still exported.
+fn export_directory(
+    context: &ExportContext,
+    operations: &ExportOperations,
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    let args = context.args;
+    let filter = MetaFileFilter::new(&args.include, &args.exclude)?;
+    let input_files = find_meta_files(&args.input_file, &filter)?;
+    if input_files.is_empty() {
+        return Err(ExportError::NoMetaFiles(args.input_file.clone()));
+    }
+    export_meta_files(context, operations, &input_files, output)
+}
+
+/// Exports `input_files`, relative to the input directory, and reports
+/// outputs of deleted files.
+fn export_meta_files(
+    context: &ExportContext,
+    operations: &ExportOperations,
+    input_files: &[PathBuf],
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    let args = context.args;
+    let root_directory = args.output_directory();
+    let mut manifest = read_manifest(root_directory);
+    let options = get_manifest_options(args, operations);
+
+    let keys: Vec<String> = input_files
+        .iter()
+        .map(|relative_path| context.manifest_key(&args.input_file.join(relative_path)))
+        .collect();
+
+    // Files are exported in parallel, outputs are merged in file order
+    let inputs: Vec<_> = input_files.iter().zip(keys.iter()).collect();
+    let exported = map_ordered(&inputs, output, |(relative_path, key), output| {
+        let input_file = args.input_file.join(relative_path);
+        let previous = manifest.inputs.get(*key);
+        let output_directory = match relative_path.parent() {
+            Some(parent) => root_directory.join(parent),
+            None => root_directory.to_path_buf(),
+        };
+
+        let mut result: Map<String, Value> = Map::new();
+        result.insert("file".to_string(), json!(input_file.to_string_lossy()));
+        let exported = fs::create_dir_all(&output_directory)
+            .map_err(|_| ExportError::ImageWrite(vec![output_directory.clone()]))
+            .and_then(|()| {
+                export_incremental(
+                    context,
+                    &input_file,
+                    &output_directory,
+                    root_directory,
+                    operations,
+                    &options,
+                    previous,
+                    &mut result,
+                    output,
+                )
+            });
+
+        let (entry, exit_code) = match exported {
+            Ok(entry) => {
+                result.insert("failure".to_string(), Value::Null);
+                (Some(entry), None)
+            }
+            Err(e) => {
+                let e = CliError::from(e);
+                output.error_message(&format!("{}: {}", input_file.to_string_lossy(), e));
+                result.insert(
+                    "failure".to_string(),
+                    json!({ "exit_code": e.exit_code(), "message": e.to_string() }),
+                );
+                (None, Some(e.exit_code()))
+            }
+        };
+        (result, entry, exit_code)
+    });
+
+    let mut failed_files: Vec<(PathBuf, u8)> = Vec::new();
+    let mut skipped_files = 0;
+    let mut summary: Vec<String> = Vec::new();
+    let mut input_results = Vec::new();
+    for ((relative_path, key), (result, entry, exit_code)) in inputs.into_iter().zip(exported) {
+        let display_path = relative_path.to_string_lossy();
+        match exit_code {
+            None if result.get("skipped") == Some(&json!(true)) => {
+                skipped_files += 1;
+                summary.push(format!("  skipped {}", display_path));
+            }
+            None => summary.push(format!("  ok      {}", display_path)),
+            Some(exit_code) => {
+                summary.push(format!("  failed  {}", display_path));
+                failed_files.push((relative_path.to_path_buf(), exit_code));
+            }
+        }
+        // A failed file keeps its previous entry so it is exported again
+        if let Some(entry) = entry {
+            manifest.inputs.insert(key.clone(), entry);
+        }
+        input_results.push(result);
+    }
+    let deleted_results = handle_deleted_inputs(context, root_directory, &mut manifest, output);
+
+    output.message(&format!(
+        "Exported {} of {} files, {} up to date",
+        input_files.len() - failed_files.len() - skipped_files,
+        input_files.len(),
+        skipped_files
+    ));
+    for line in summary {
+        output.message(&line);
+    }
+    output.set_result("inputs", input_results);
+    output.set_result("deleted_inputs", deleted_results);
+    output.set_result(
+        "exported_files",
+        input_files.len() - failed_files.len() - skipped_files,
+    );
+    output.set_result("skipped_files", skipped_files);
+    output.set_result("failed_files", failed_files.len());
+
+    let exported = match failed_files.is_empty() {
+        true => Ok(()),
+        false => Err(ExportError::FilesFailed((failed_files, input_files.len()))),
+    };
+    save_manifest(root_directory, &manifest, exported, output)
+}
+
+/// Outputs of manifest entries whose .meta file doesn't exist anymore are
+/// stale. Entries are dropped once none of their outputs is left.
+fn handle_deleted_inputs(
+    context: &ExportContext,
+    root_directory: &Path,
+    manifest: &mut ExportManifest,
+    output: &mut Output,
+) -> Vec<Value> {
+    let args = context.args;
+    // Only inputs of the exported directory, other entries of the manifest
+    // belong to other exports into the same output directory
+    let directory_key = context.manifest_key(&args.input_file);
+    let deleted: Vec<(String, PathBuf)> = manifest
+        .inputs
+        .keys()
+        .filter_map(|input| {
+            let relative_path = match directory_key.is_empty() {
+                true => input.as_str(),
+                false => input.strip_prefix(&directory_key)?.strip_prefix('/')?,
+            };
+            let input_file = args.input_file.join(relative_path);
+            (!input_file.exists()).then(|| (input.clone(), input_file))
+        })
+        .collect();
+
+    let mut deleted_results = Vec::new();
+    for (input, input_file) in deleted {
+        let Some(entry) = manifest.inputs.get_mut(&input) else {
+            continue;
+        };
+        let mut result: Map<String, Value> = Map::new();
+        result.insert("file".to_string(), json!(input_file.to_string_lossy()));
+        let stale = entry.existing_files(root_directory);
+        entry.outputs.clear();
+        entry.stale = handle_stale_outputs(
+            args,
+            &input_file,
+            root_directory,
+            stale,
+            &mut result,
+            output,
+        );
+        if entry.stale.is_empty() {
+            manifest.inputs.shift_remove(&input);
+        }
+        if result.len() > 1 {
+            deleted_results.push(Value::Object(result));
+        }
+    }
+    deleted_results
+}
+
+pub fn run(mut args: ExportArgs, output: &mut Output) -> Result<(), ExportError> {
+    let config = load_project_config_for(&args.config, &args.input_file)?;
+    args.apply_config(&config.export);
+    let name_template = NameTemplate::parse(args.name_template())
+        .map_err(|e| ExportError::InvalidNameTemplate((args.name_template().to_string(), e)))?;
 
     let process_args = ProcessArgs {
         color: args.color,
@@ -164,24 +1117,134 @@ pub fn run(args: ExportArgs) -> Result<(), ExportError> {
         all: args.all,
     };
     let operations = process_operations(&process_args)?;
+    if args.layout() == Layout::Lut && !args.variant.is_empty() {
+        return Err(ExportError::VariantWithLut);
+    }
 
-    for operation in operations {
-        let ExportOperation::Export(material_type) = operation;
-        let colors = get_colors_from_palette(&palette, &material_type);
-        let varians_suffix = if let Some(variant) = &args.variant {
-            format!("-{}", variant)
-        } else {
-            "".to_string()
-        };
-        let full_filename = format!("{}-{}{}.png", &filename, &material_type, varians_suffix);
-        let mut output_directory: PathBuf = args.output_directory.clone().into();
-        match save_image(&colors, &mut output_directory, &full_filename) {
-            Ok(SaveImageSuccess::SaveOk(message)) => {
-                if args.verbose {
-                    println!("{}", message);
-                }
+    if args.input_file.is_dir() && args.filename.is_some() {
+        return Err(ExportError::FilenameWithDirectory);
+    }
+    if args.watch && output.is_json() {
+        return Err(ExportError::WatchWithJson);
+    }
+
+    let context = ExportContext {
+        args: &args,
+        name_template,
+        input_root: config.root_directory(),
+    };
+    run_with_jobs(args.jobs, || {
+        let exported = export_input(&context, &operations, output);
+        if !args.watch {
+            return exported;
+        }
+        if let Err(e) = exported {
+            output.error_message(&CliError::from(e).to_string());
+        }
+        watch(&context, &operations, output)
+    })
+}
+
+fn export_input(
+    context: &ExportContext,
+    operations: &ExportOperations,
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    let args = context.args;
+    if args.input_file.is_dir() {
+        return export_directory(context, operations, output);
+    }
+
+    let root_directory = args.output_directory();
+    fs::create_dir_all(root_directory)
+        .map_err(|_| ExportError::ImageWrite(vec![root_directory.to_path_buf()]))?;
+    let mut manifest = read_manifest(root_directory);
+    let input = context.manifest_key(&args.input_file);
+    let mut result: Map<String, Value> = Map::new();
+    let exported = export_incremental(
+        context,
+        &args.input_file,
+        root_directory,
+        root_directory,
+        operations,
+        &get_manifest_options(args, operations),
+        manifest.inputs.get(&input),
+        &mut result,
+        output,
+    );
+    if result.get("skipped") == Some(&json!(true)) {
+        output.message(&format!(
+            "{} is up to date, use --force to export it again",
+            args.input_file.to_string_lossy()
+        ));
+    }
+    for (key, value) in result {
+        output.set_result(&key, value);
+    }
+    // A failed export keeps the previous entry, so the manifest isn't written
+    manifest.inputs.insert(input, exported?);
+    save_manifest(root_directory, &manifest, Ok(()), output)
+}
+
+/// Exports changed .meta files after every save until the process is stopped.
+/// A file which fails to export is reported and its previous outputs are kept.
+fn watch(
+    context: &ExportContext,
+    operations: &ExportOperations,
+    output: &mut Output,
+) -> Result<(), ExportError> {
+    let args = context.args;
+    let is_directory = args.input_file.is_dir();
+    // Editors save by replacing the file, so a single file is watched through
+    // its directory
+    let (directory, filter) = match is_directory {
+        true => (
+            args.input_file.clone(),
+            MetaFileFilter::new(&args.include, &args.exclude)?,
+        ),
+        false => {
+            let file_name = args.input_file.file_name().unwrap_or_default();
+            let directory = match args.input_file.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            };
+            let include = vec![globset::escape(&file_name.to_string_lossy())];
+            (directory, MetaFileFilter::new(&include, &[])?)
+        }
+    };
+    let watcher = DirectoryWatcher::new(&directory, is_directory)
+        .map_err(|e| ExportError::Watch((directory.clone(), e.to_string())))?;
+    output.message(&format!(
+        "Watching {} for changes, press Ctrl+C to stop",
+        args.input_file.to_string_lossy()
+    ));
+
+    while let Some(changed) = watcher.next_batch(WATCH_DEBOUNCE) {
+        let changed: Vec<PathBuf> = changed
+            .into_iter()
+            .filter(|relative_path| filter.matches(relative_path))
+            .collect();
+        if changed.is_empty() {
+            continue;
+        }
+        for relative_path in changed.iter() {
+            output.message(&format!("Changed {}", relative_path.to_string_lossy()));
+        }
+
+        let exported = match is_directory {
+            true => {
+                let existing: Vec<PathBuf> = changed
+                    .into_iter()
+                    .filter(|relative_path| directory.join(relative_path).is_file())
+                    .collect();
+                export_meta_files(context, operations, &existing, output)
             }
-            Err(e) => println!("Saving failed {:?}", e),
+            false if args.input_file.is_file() => export_input(context, operations, output),
+            // Removed while saving, the next change exports it again
+            false => continue,
+        };
+        if let Err(e) = exported {
+            output.error_message(&CliError::from(e).to_string());
         }
     }
     Ok(())
@@ -0,0 +1,155 @@
+use crate::utils::get_colors_from_meta::{get_colors_from_palette, MaterialProperty};
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::output::Output;
+use crate::utils::palette::{get_palette_from_meta, GetPaletteError};
+use crate::utils::report::FileReport;
+use crate::utils::save_image::{build_palette_image, get_palette_grid};
+use crate::utils::terminal_image::{
+    detect_graphics_protocol, render_blocks, render_kitty, render_sixel, GraphicsProtocol,
+};
+use crate::utils::variants::{suggest_variant_paths, VariantPath};
+use clap::{Parser, ValueEnum};
+use hex::encode;
+use std::fs;
+use std::path::PathBuf;
+
+/// Pixels per material in Kitty and Sixel images
+const INSPECT_TILE_SIZE: u32 = 24;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Graphics {
+    /// Kitty or Sixel when the terminal is known to support it, blocks otherwise
+    Auto,
+    Blocks,
+    Kitty,
+    Sixel,
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Show the palette of a .meta file in the terminal.",
+    long_about = "Renders the palette grid of a material property in the terminal, laid out like the exported textures. Uses truecolor blocks, or Kitty and Sixel graphics when the terminal supports them"
+)]
+pub struct InspectArgs {
+    input_file: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = MaterialProperty::Color)]
+    property: MaterialProperty,
+
+    /// Variant name, or path like foo/bar/deep when the name is not unique
+    #[arg(long)]
+    variant: Option<String>,
+
+    /// Match variant names regardless of letter case
+    #[arg(long, default_value_t = false)]
+    ignore_case: bool,
+
+    #[arg(short, long, value_enum, default_value_t = Graphics::Auto)]
+    graphics: Graphics,
+
+    /// Write material indexes into the blocks
+    #[arg(short, long, default_value_t = false)]
+    labels: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum InspectError {
+    FileRead,
+    JsonParse(String),
+    InvalidVariantName((String, Vec<VariantPath>)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    MaterialIndexOutOfRange(u8),
+    InvalidPaletteSize(usize),
+}
+
+impl From<std::io::Error> for InspectError {
+    fn from(_e: std::io::Error) -> Self {
+        InspectError::FileRead
+    }
+}
+
+fn graphics_protocol(graphics: Graphics) -> GraphicsProtocol {
+    match graphics {
+        Graphics::Auto => detect_graphics_protocol(
+            &std::env::var("TERM").unwrap_or_default(),
+            &std::env::var("TERM_PROGRAM").unwrap_or_default(),
+        ),
+        Graphics::Blocks => GraphicsProtocol::Blocks,
+        Graphics::Kitty => GraphicsProtocol::Kitty,
+        Graphics::Sixel => GraphicsProtocol::Sixel,
+    }
+}
+
+pub fn run(args: InspectArgs, output: &mut Output) -> Result<(), InspectError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| InspectError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    let palette = match get_palette_from_meta(&meta, &args.variant, args.ignore_case) {
+        Ok(palette) => palette,
+        Err(GetPaletteError::MaterialIndexOutOfRange(index)) => {
+            return Err(InspectError::MaterialIndexOutOfRange(index));
+        }
+        Err(GetPaletteError::AmbiguousVariant(paths)) => {
+            let variant_name = args.variant.unwrap_or_default();
+            return Err(InspectError::AmbiguousVariant((variant_name, paths)));
+        }
+        Err(_) => {
+            let variant_name = args.variant.unwrap_or_default();
+            let suggestions = suggest_variant_paths(&meta, &variant_name);
+            return Err(InspectError::InvalidVariantName((
+                variant_name,
+                suggestions,
+            )));
+        }
+    };
+
+    let property = args.property;
+    let colors = get_colors_from_palette(&palette, &property);
+    let grid = get_palette_grid(colors.len())
+        .map_err(|_| InspectError::InvalidPaletteSize(colors.len()))?;
+
+    let mut title = format!("{} {}", args.input_file.to_string_lossy(), property);
+    if let Some(variant) = &args.variant {
+        title.push_str(&format!(" ({})", variant));
+    }
+    output.set_result("property", property.to_string());
+    output.set_result("variant", &args.variant);
+    output.set_result(
+        "grid",
+        grid.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|index| encode(colors[*index]))
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<_>>(),
+    );
+    if output.is_json() {
+        return Ok(());
+    }
+    output.message(&title);
+
+    let text = match graphics_protocol(args.graphics) {
+        GraphicsProtocol::Blocks => render_blocks(&colors, &grid, args.labels),
+        protocol => {
+            let image = build_palette_image(&colors, INSPECT_TILE_SIZE)
+                .map_err(|_| InspectError::InvalidPaletteSize(colors.len()))?;
+            match protocol {
+                GraphicsProtocol::Kitty => render_kitty(&image, grid.len() * 4, grid.len() * 2),
+                _ => render_sixel(&image),
+            }
+        }
+    };
+    output.print(&text);
+    Ok(())
+}
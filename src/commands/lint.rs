@@ -0,0 +1,181 @@
+use crate::utils::config::{
+    load_project_configs, rule_levels_from_flags, ConfigError, ProjectConfig,
+};
+use crate::utils::diagnostic::{apply_rule_levels, has_errors, RuleLevels, Severity, WARNINGS};
+use crate::utils::jobs::{map_ordered, run_with_jobs};
+use crate::utils::lenient_parse::{parse_meta, LENIENT_REPAIR};
+use crate::utils::lint::{lint_meta, LINT_RULES};
+use crate::utils::output::Output;
+use crate::utils::report::{write_reports, FileReport};
+use clap::builder::PossibleValuesParser;
+use clap::Parser;
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn rule_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        LINT_RULES
+            .iter()
+            .map(|rule| rule.id)
+            .chain([LENIENT_REPAIR, WARNINGS]),
+    )
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Check .meta files for common art mistakes.",
+    long_about = "Reports similar colors, dark emissive materials, glassiness without specular, emissive materials with company tint and redundant variant overrides. Rule levels and thresholds are read from vt-utils.toml found in the directory of each input or its parents, command line flags take precedence. Exits with non-zero status when any rule set to deny is reported"
+)]
+pub struct LintArgs {
+    #[arg(required_unless_present = "list_rules")]
+    input_files: Vec<PathBuf>,
+
+    /// Suppress the rule
+    #[arg(short = 'A', long, value_parser = rule_parser())]
+    allow: Vec<String>,
+
+    /// Report the rule as warning
+    #[arg(short = 'W', long, value_parser = rule_parser())]
+    warn: Vec<String>,
+
+    /// Report the rule as error, `--deny warnings` turns every warning into error
+    #[arg(short = 'D', long, value_parser = rule_parser())]
+    deny: Vec<String>,
+
+    /// Colors closer than this CIEDE2000 distance are reported as similar [default: 2.3]
+    #[arg(long)]
+    delta_e: Option<f64>,
+
+    /// Emissive colors with L* lightness (0-100) below this value are reported as dark [default: 20]
+    #[arg(long)]
+    dark_lightness: Option<f64>,
+
+    /// Config file to use instead of vt-utils.toml found from each input
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Write findings as SARIF, e.g. for GitHub code scanning
+    #[arg(long, value_name = "FILE")]
+    sarif: Option<PathBuf>,
+
+    /// Write findings as JUnit XML
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+
+    /// Print available rules and exit
+    #[arg(long, default_value_t = false)]
+    list_rules: bool,
+
+    /// Number of files checked in parallel, one per CPU core by default
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+}
+
+#[derive(Debug)]
+pub enum LintError {
+    Config(ConfigError),
+    ReportWrite,
+    LintFailed(usize),
+}
+
+impl From<ConfigError> for LintError {
+    fn from(e: ConfigError) -> Self {
+        LintError::Config(e)
+    }
+}
+
+/// Lints one file, returns its report and whether it failed.
+fn lint_file(
+    args: &LintArgs,
+    config: &ProjectConfig,
+    flag_levels: &RuleLevels,
+    input_file: &Path,
+    output: &mut Output,
+) -> (FileReport, bool) {
+    let display_path = input_file.to_string_lossy();
+    let content = match fs::read_to_string(input_file) {
+        Ok(content) => content,
+        Err(_) => {
+            output.message(&format!(
+                "{}: {}: Failed to read the file",
+                display_path,
+                Severity::Error
+            ));
+            let report = FileReport::read_error(input_file);
+            output.collect(&report);
+            return (report, true);
+        }
+    };
+    let (meta, repairs) = match parse_meta(&content, args.lenient) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            output.print(&e.render(input_file, &content));
+            let report = FileReport::parse_error(input_file, &e);
+            output.collect(&report);
+            return (report, true);
+        }
+    };
+
+    let mut file_config = config.for_file(input_file);
+    file_config.rules.extend(flag_levels.clone());
+    let mut settings = file_config.lint.to_settings();
+    if let Some(delta_e) = args.delta_e {
+        settings.similar_colors_delta_e = delta_e;
+    }
+    if let Some(dark_lightness) = args.dark_lightness {
+        settings.dark_emission_lightness = dark_lightness;
+    }
+
+    let mut diagnostics = repairs;
+    diagnostics.extend(lint_meta(&meta, &settings));
+    let diagnostics = apply_rule_levels(diagnostics, &file_config.rules);
+    let report = FileReport::from_diagnostics(input_file, &content, &diagnostics);
+    output.report(&report);
+    (report, has_errors(&diagnostics))
+}
+
+pub fn run(args: LintArgs, output: &mut Output) -> Result<(), LintError> {
+    if args.list_rules {
+        for rule in LINT_RULES.iter() {
+            output.message(&format!("{:<28} {}", rule.id, rule.description));
+        }
+        output.set_result(
+            "rules",
+            LINT_RULES
+                .iter()
+                .map(|rule| json!({ "id": rule.id, "description": rule.description }))
+                .collect::<Vec<_>>(),
+        );
+        return Ok(());
+    }
+
+    let configs = load_project_configs(&args.config, &args.input_files)?;
+    let inputs: Vec<_> = args.input_files.iter().zip(configs.iter()).collect();
+    let flag_levels = rule_levels_from_flags(&args.allow, &args.warn, &args.deny);
+
+    let checked = run_with_jobs(args.jobs, || {
+        map_ordered(&inputs, output, |(input_file, config), output| {
+            lint_file(&args, config, &flag_levels, input_file, output)
+        })
+    });
+    let failed_files = checked.iter().filter(|(_, failed)| *failed).count();
+    let reports: Vec<FileReport> = checked.into_iter().map(|(report, _)| report).collect();
+
+    write_reports("vt-utils lint", &reports, &args.sarif, &args.junit)
+        .map_err(|_| LintError::ReportWrite)?;
+    for report_file in args.sarif.iter().chain(args.junit.iter()) {
+        output.file_written(report_file);
+    }
+
+    output.set_result("checked_files", args.input_files.len());
+    output.set_result("failed_files", failed_files);
+    if failed_files > 0 {
+        return Err(LintError::LintFailed(failed_files));
+    }
+    Ok(())
+}
@@ -1 +1,11 @@
+pub mod config;
+pub mod dedupe;
+pub mod edit;
+pub mod explain;
 pub mod export;
+pub mod inspect;
+pub mod lint;
+pub mod schema;
+pub mod validate;
+pub mod variant;
+pub mod variants;
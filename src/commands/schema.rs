@@ -0,0 +1,40 @@
+use crate::utils::json_schema::get_meta_json_schema;
+use crate::utils::output::Output;
+use crate::utils::write_meta::{serialize_meta_json, write_meta_file, WriteMetaError};
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Print JSON Schema of .meta files.",
+    long_about = "Prints JSON Schema of .meta files which can be used by code editors for validation and autocompletion"
+)]
+pub struct SchemaArgs {
+    /// Write schema to file instead of standard output
+    output_file: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    FileWrite,
+}
+
+impl From<WriteMetaError> for SchemaError {
+    fn from(_e: WriteMetaError) -> Self {
+        SchemaError::FileWrite
+    }
+}
+
+pub fn run(args: SchemaArgs, output: &mut Output) -> Result<(), SchemaError> {
+    let schema = get_meta_json_schema();
+
+    if let Some(output_file) = args.output_file {
+        write_meta_file(&output_file, &schema)?;
+        output.file_written(&output_file);
+    } else if output.is_json() {
+        output.set_result("schema", &schema);
+    } else {
+        output.message(&serialize_meta_json(&schema)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use crate::utils::config::{
+    load_project_configs, rule_levels_from_flags, ConfigError, ProjectConfig,
+};
+use crate::utils::diagnostic::{apply_rule_levels, has_errors, RuleLevels, Severity, WARNINGS};
+use crate::utils::jobs::{map_ordered, run_with_jobs};
+use crate::utils::lenient_parse::{parse_meta, LENIENT_REPAIR};
+use crate::utils::output::Output;
+use crate::utils::report::{write_reports, FileReport};
+use crate::utils::validate::{validate_meta, VALIDATION_RULES};
+use crate::utils::write_meta::write_meta_file;
+use clap::builder::PossibleValuesParser;
+use clap::Parser;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn rule_parser() -> PossibleValuesParser {
+    PossibleValuesParser::new(
+        VALIDATION_RULES
+            .into_iter()
+            .chain([LENIENT_REPAIR, WARNINGS]),
+    )
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Check .meta files for structural errors.",
+    long_about = "Checks palette size, material indexes used by variants, color values and variant names. Rule levels are read from vt-utils.toml found in the directory of each input or its parents, command line flags take precedence. Exits with non-zero status when any error is found"
+)]
+pub struct ValidateArgs {
+    #[arg(required = true)]
+    input_files: Vec<PathBuf>,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+
+    /// Save repaired files in place, implies --lenient
+    #[arg(long, default_value_t = false)]
+    fix: bool,
+
+    /// Suppress the rule
+    #[arg(short = 'A', long, value_parser = rule_parser())]
+    allow: Vec<String>,
+
+    /// Report the rule as warning
+    #[arg(short = 'W', long, value_parser = rule_parser())]
+    warn: Vec<String>,
+
+    /// Report the rule as error, `--deny warnings` turns every warning into error
+    #[arg(short = 'D', long, value_parser = rule_parser())]
+    deny: Vec<String>,
+
+    /// Config file to use instead of vt-utils.toml found from each input
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Write findings as SARIF, e.g. for GitHub code scanning
+    #[arg(long, value_name = "FILE")]
+    sarif: Option<PathBuf>,
+
+    /// Write findings as JUnit XML
+    #[arg(long, value_name = "FILE")]
+    junit: Option<PathBuf>,
+
+    /// Number of files checked in parallel, one per CPU core by default
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+}
+
+#[derive(Debug)]
+pub enum ValidateError {
+    Config(ConfigError),
+    ReportWrite,
+    ValidationFailed(usize),
+}
+
+impl From<ConfigError> for ValidateError {
+    fn from(e: ConfigError) -> Self {
+        ValidateError::Config(e)
+    }
+}
+
+/// Checks one file, returns its report and whether it failed.
+fn validate_file(
+    args: &ValidateArgs,
+    config: &ProjectConfig,
+    flag_levels: &RuleLevels,
+    input_file: &Path,
+    output: &mut Output,
+) -> (FileReport, bool) {
+    let display_path = input_file.to_string_lossy();
+    let content = match fs::read_to_string(input_file) {
+        Ok(content) => content,
+        Err(_) => {
+            output.message(&format!(
+                "{}: {}: Failed to read the file",
+                display_path,
+                Severity::Error
+            ));
+            let report = FileReport::read_error(input_file);
+            output.collect(&report);
+            return (report, true);
+        }
+    };
+    let (meta, repairs) = match parse_meta(&content, args.lenient || args.fix) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            output.print(&e.render(input_file, &content));
+            let report = FileReport::parse_error(input_file, &e);
+            output.collect(&report);
+            return (report, true);
+        }
+    };
+
+    let mut levels = config.for_file(input_file).rules;
+    levels.extend(flag_levels.clone());
+
+    let has_repairs = !repairs.is_empty();
+    let mut diagnostics = repairs;
+    diagnostics.extend(validate_meta(&meta));
+    let diagnostics = apply_rule_levels(diagnostics, &levels);
+    let report = FileReport::from_diagnostics(input_file, &content, &diagnostics);
+    output.report(&report);
+    let mut failed = has_errors(&diagnostics);
+
+    if args.fix && has_repairs {
+        match write_meta_file(input_file, &meta) {
+            Ok(()) => {
+                output.message(&format!("{}: Saved repaired file", display_path));
+                output.file_written(input_file);
+            }
+            Err(_) => {
+                output.message(&format!(
+                    "{}: {}: Failed to write the file",
+                    display_path,
+                    Severity::Error
+                ));
+                output.collect(&FileReport::write_error(input_file));
+                failed = true;
+            }
+        }
+    }
+    (report, failed)
+}
+
+pub fn run(args: ValidateArgs, output: &mut Output) -> Result<(), ValidateError> {
+    let configs = load_project_configs(&args.config, &args.input_files)?;
+    let inputs: Vec<_> = args.input_files.iter().zip(configs.iter()).collect();
+    let flag_levels = rule_levels_from_flags(&args.allow, &args.warn, &args.deny);
+    let checked = run_with_jobs(args.jobs, || {
+        map_ordered(&inputs, output, |(input_file, config), output| {
+            validate_file(&args, config, &flag_levels, input_file, output)
+        })
+    });
+    let failed_files = checked.iter().filter(|(_, failed)| *failed).count();
+    let reports: Vec<FileReport> = checked.into_iter().map(|(report, _)| report).collect();
+
+    write_reports("vt-utils validate", &reports, &args.sarif, &args.junit)
+        .map_err(|_| ValidateError::ReportWrite)?;
+    for report_file in args.sarif.iter().chain(args.junit.iter()) {
+        output.file_written(report_file);
+    }
+
+    output.set_result("checked_files", args.input_files.len());
+    output.set_result("failed_files", failed_files);
+    if failed_files > 0 {
+        return Err(ValidateError::ValidationFailed(failed_files));
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use crate::utils::lenient_parse::{parse_meta, parse_variants};
+use crate::utils::output::Output;
+use crate::utils::report::FileReport;
+use crate::utils::variant_transfer::{extract_variant, inject_variants, VariantTransferError};
+use crate::utils::variants::VariantPath;
+use crate::utils::write_meta::{write_meta_file, WriteMetaError};
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    about = "Move variants between .meta files.",
+    long_about = "Extracts a variant subtree with its overrides into a standalone snippet file and injects such snippets into other .meta files"
+)]
+pub struct VariantArgs {
+    #[command(subcommand)]
+    command: VariantCommands,
+}
+
+#[derive(Subcommand)]
+enum VariantCommands {
+    Extract(ExtractArgs),
+    Inject(InjectArgs),
+}
+
+#[derive(Parser)]
+#[command(about = "Write a variant subtree into a snippet file.")]
+struct ExtractArgs {
+    input_file: PathBuf,
+
+    variant: String,
+
+    /// Snippet file to write, defaults to <variant>.variant.json
+    output_file: Option<PathBuf>,
+
+    /// Bake overrides of parent variants into the extracted variant
+    #[arg(long, default_value_t = false)]
+    flatten: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Parser)]
+#[command(about = "Graft variants from a snippet file into a .meta file.")]
+struct InjectArgs {
+    snippet_file: PathBuf,
+
+    target_file: PathBuf,
+
+    /// Variant under which the snippet is placed, top level when omitted
+    #[arg(short, long)]
+    parent: Option<String>,
+
+    /// Write the result to another file instead of modifying the target
+    #[arg(short, long)]
+    output_file: Option<PathBuf>,
+
+    /// Replace variants that already exist in the target
+    #[arg(long, default_value_t = false)]
+    replace: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum VariantError {
+    FileRead,
+    FileWrite,
+    JsonParse(String),
+    InvalidVariantName((String, Vec<VariantPath>)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    VariantAlreadyExist(String),
+    EmptySnippet,
+    InvalidMaterialIndexes(Vec<(VariantPath, u8)>),
+}
+
+impl From<std::io::Error> for VariantError {
+    fn from(_e: std::io::Error) -> Self {
+        VariantError::FileRead
+    }
+}
+
+impl From<WriteMetaError> for VariantError {
+    fn from(_e: WriteMetaError) -> Self {
+        VariantError::FileWrite
+    }
+}
+
+impl From<VariantTransferError> for VariantError {
+    fn from(e: VariantTransferError) -> Self {
+        match e {
+            VariantTransferError::VariantNotExist(not_exist) => {
+                VariantError::InvalidVariantName(not_exist)
+            }
+            VariantTransferError::AmbiguousVariant(ambiguous) => {
+                VariantError::AmbiguousVariant(ambiguous)
+            }
+            VariantTransferError::VariantAlreadyExist(name) => {
+                VariantError::VariantAlreadyExist(name)
+            }
+            VariantTransferError::EmptySnippet => VariantError::EmptySnippet,
+            VariantTransferError::InvalidMaterialIndexes(indexes) => {
+                VariantError::InvalidMaterialIndexes(indexes)
+            }
+        }
+    }
+}
+
+fn run_extract(args: ExtractArgs, output: &mut Output) -> Result<(), VariantError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| VariantError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    let snippet = extract_variant(&meta, &args.variant, args.flatten)?;
+    let output_file = args.output_file.unwrap_or_else(|| {
+        let name = snippet.keys().next().unwrap_or(&args.variant);
+        PathBuf::from(format!("{}.variant.json", name))
+    });
+    write_meta_file(&output_file, &snippet)?;
+    output.file_written(&output_file);
+    output.set_result("variants", snippet.keys().collect::<Vec<_>>());
+
+    output.message(&format!(
+        "Succesfully extracted {} to {}",
+        args.variant,
+        output_file.to_string_lossy()
+    ));
+    Ok(())
+}
+
+fn run_inject(args: InjectArgs, output: &mut Output) -> Result<(), VariantError> {
+    let snippet_content = fs::read_to_string(&args.snippet_file)?;
+    let (snippet, snippet_repairs) = parse_variants(&snippet_content, args.lenient)
+        .map_err(|e| VariantError::JsonParse(e.render(&args.snippet_file, &snippet_content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.snippet_file,
+        &snippet_content,
+        &snippet_repairs,
+    ));
+    let target_content = fs::read_to_string(&args.target_file)?;
+    let (mut meta, target_repairs) = parse_meta(&target_content, args.lenient)
+        .map_err(|e| VariantError::JsonParse(e.render(&args.target_file, &target_content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.target_file,
+        &target_content,
+        &target_repairs,
+    ));
+
+    let names: Vec<String> = snippet.keys().cloned().collect();
+    inject_variants(&mut meta, snippet, &args.parent, args.replace)?;
+    let output_file = args.output_file.unwrap_or(args.target_file);
+    write_meta_file(&output_file, &meta)?;
+    output.file_written(&output_file);
+
+    output.message(&format!(
+        "Succesfully injected {} into {}",
+        names.join(", "),
+        output_file.to_string_lossy()
+    ));
+    output.set_result("variants", names);
+    Ok(())
+}
+
+pub fn run(args: VariantArgs, output: &mut Output) -> Result<(), VariantError> {
+    match args.command {
+        VariantCommands::Extract(args) => run_extract(args, output),
+        VariantCommands::Inject(args) => run_inject(args, output),
+    }
+}
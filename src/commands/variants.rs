@@ -0,0 +1,85 @@
+use crate::utils::lenient_parse::parse_meta;
+use crate::utils::output::Output;
+use crate::utils::report::FileReport;
+use crate::utils::variant_tree::{
+    render_variant_tree_dot, render_variant_tree_json, render_variant_tree_mermaid,
+    render_variant_tree_text, variant_tree_json,
+};
+use crate::utils::variants::get_variant_tree_from_meta;
+use clap::{Parser, ValueEnum};
+use std::fs;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TreeFormat {
+    Text,
+    Json,
+    Dot,
+    Mermaid,
+}
+
+#[derive(Parser)]
+#[command(
+    about = "Print the variant hierarchy of a .meta file.",
+    long_about = "Prints all variants as a tree in file order with the number of overridden materials and swatches of their colors. The tree can also be printed as JSON, Graphviz dot or Mermaid flowchart for documentation"
+)]
+pub struct VariantsArgs {
+    input_file: PathBuf,
+
+    #[arg(short, long, value_enum, default_value_t = TreeFormat::Text)]
+    format: TreeFormat,
+
+    /// Print hex codes instead of color swatches
+    #[arg(long, default_value_t = false)]
+    no_color: bool,
+
+    /// Accept and repair common mistakes like # prefixed colors or trailing commas
+    #[arg(long, default_value_t = false)]
+    lenient: bool,
+}
+
+#[derive(Debug)]
+pub enum VariantsError {
+    FileRead,
+    JsonParse(String),
+}
+
+impl From<std::io::Error> for VariantsError {
+    fn from(_e: std::io::Error) -> Self {
+        VariantsError::FileRead
+    }
+}
+
+pub fn run(args: VariantsArgs, output: &mut Output) -> Result<(), VariantsError> {
+    let content = fs::read_to_string(&args.input_file)?;
+    let (meta, repairs) = parse_meta(&content, args.lenient)
+        .map_err(|e| VariantsError::JsonParse(e.render(&args.input_file, &content)))?;
+    output.report_repairs(&FileReport::from_diagnostics(
+        &args.input_file,
+        &content,
+        &repairs,
+    ));
+
+    let tree = get_variant_tree_from_meta(&meta);
+    output.set_result("variants", variant_tree_json(&tree));
+    let root = args
+        .input_file
+        .file_name()
+        .unwrap_or(args.input_file.as_os_str())
+        .to_string_lossy();
+
+    let text = match args.format {
+        TreeFormat::Text => {
+            let use_color = !args.no_color
+                && std::env::var_os("NO_COLOR").is_none()
+                && std::io::stdout().is_terminal();
+            render_variant_tree_text(&root, &tree, use_color)
+        }
+        TreeFormat::Json => format!("{}\n", render_variant_tree_json(&tree)),
+        TreeFormat::Dot => render_variant_tree_dot(&root, &tree),
+        TreeFormat::Mermaid => render_variant_tree_mermaid(&root, &tree),
+    };
+    output.print(&text);
+    Ok(())
+}
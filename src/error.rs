@@ -0,0 +1,463 @@
+use core::fmt;
+use std::process::ExitCode;
+
+use crate::commands::config::ConfigCommandError;
+use crate::commands::dedupe::DedupeError;
+use crate::commands::edit::EditError;
+use crate::commands::explain::ExplainError;
+use crate::commands::export::ExportError;
+use crate::commands::inspect::InspectError;
+use crate::commands::lint::LintError;
+use crate::commands::schema::SchemaError;
+use crate::commands::validate::ValidateError;
+use crate::commands::variant::VariantError;
+use crate::commands::variants::VariantsError;
+use crate::utils::name_template::{NameTemplateError, NAME_PLACEHOLDERS};
+use crate::utils::variant_select::is_variant_pattern;
+use crate::utils::variants::{format_variant_path, VariantPath};
+
+/// `validate` or `lint` reported an error
+pub const EXIT_CHECK_FAILED: u8 = 1;
+/// Invalid arguments, same code as argument errors reported by clap
+pub const EXIT_USAGE: u8 = 2;
+/// An input file can't be read
+pub const EXIT_INPUT_READ: u8 = 3;
+/// An input file is not a valid .meta file
+pub const EXIT_INVALID_INPUT: u8 = 4;
+/// An output file can't be written
+pub const EXIT_OUTPUT_WRITE: u8 = 5;
+/// `vt-utils.toml` can't be read or is invalid
+pub const EXIT_CONFIG: u8 = 6;
+
+#[derive(Debug)]
+pub enum CliError {
+    Export(ExportError),
+    Dedupe(DedupeError),
+    Variant(VariantError),
+    Variants(VariantsError),
+    Inspect(InspectError),
+    Explain(ExplainError),
+    Edit(EditError),
+    Validate(ValidateError),
+    Lint(LintError),
+    Schema(SchemaError),
+    Config(ConfigCommandError),
+}
+
+impl From<ExportError> for CliError {
+    fn from(e: ExportError) -> Self {
+        CliError::Export(e)
+    }
+}
+
+impl From<DedupeError> for CliError {
+    fn from(e: DedupeError) -> Self {
+        CliError::Dedupe(e)
+    }
+}
+
+impl From<VariantError> for CliError {
+    fn from(e: VariantError) -> Self {
+        CliError::Variant(e)
+    }
+}
+
+impl From<VariantsError> for CliError {
+    fn from(e: VariantsError) -> Self {
+        CliError::Variants(e)
+    }
+}
+
+impl From<InspectError> for CliError {
+    fn from(e: InspectError) -> Self {
+        CliError::Inspect(e)
+    }
+}
+
+impl From<ExplainError> for CliError {
+    fn from(e: ExplainError) -> Self {
+        CliError::Explain(e)
+    }
+}
+
+impl From<EditError> for CliError {
+    fn from(e: EditError) -> Self {
+        CliError::Edit(e)
+    }
+}
+
+impl From<ValidateError> for CliError {
+    fn from(e: ValidateError) -> Self {
+        CliError::Validate(e)
+    }
+}
+
+impl From<LintError> for CliError {
+    fn from(e: LintError) -> Self {
+        CliError::Lint(e)
+    }
+}
+
+impl From<SchemaError> for CliError {
+    fn from(e: SchemaError) -> Self {
+        CliError::Schema(e)
+    }
+}
+
+impl From<ConfigCommandError> for CliError {
+    fn from(e: ConfigCommandError) -> Self {
+        CliError::Config(e)
+    }
+}
+
+const FILE_READ_MESSAGE: &str = "Failed to read the file";
+const FILE_WRITE_MESSAGE: &str = "Failed to write the file";
+const REPORT_WRITE_MESSAGE: &str = "Failed to write the report file";
+
+fn write_invalid_variant(
+    f: &mut fmt::Formatter,
+    variant: &str,
+    suggestions: &[VariantPath],
+) -> fmt::Result {
+    write!(f, "Invalid variant name: {}", variant)?;
+    if suggestions.is_empty() {
+        return write!(f, "\nUse variants command to list all variants");
+    }
+    write!(f, "\nDid you mean:")?;
+    for path in suggestions {
+        write!(f, "\n  {}", format_variant_path(path))?;
+    }
+    let differs_in_case = suggestions.iter().any(|path| {
+        format_variant_path(path).eq_ignore_ascii_case(variant)
+            || path
+                .last()
+                .is_some_and(|name| name.eq_ignore_ascii_case(variant))
+    });
+    if differs_in_case {
+        write!(
+            f,
+            "\nVariant names are case sensitive, use --ignore-case to match any case"
+        )?;
+    }
+    Ok(())
+}
+
+fn write_ambiguous(f: &mut fmt::Formatter, variant: &str, paths: &[VariantPath]) -> fmt::Result {
+    write!(f, "Ambiguous variant name: {}", variant)?;
+    write!(f, "\nUse one of the variant paths instead:")?;
+    for path in paths {
+        write!(f, "\n  {}", format_variant_path(path))?;
+    }
+    Ok(())
+}
+
+fn write_index_out_of_range(f: &mut fmt::Formatter, index: &u8) -> fmt::Result {
+    write!(
+        f,
+        "Variant overrides material {} which is missing in the palette. Use validate command for details",
+        index
+    )
+}
+
+fn write_invalid_palette_size(f: &mut fmt::Formatter, size: &usize) -> fmt::Result {
+    write!(
+        f,
+        "Palette has {} materials, only 16 or 64 are supported. Use validate command for details",
+        size
+    )
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Export(e) => match e {
+                ExportError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                ExportError::NoOperations => write!(
+                    f,
+                    "Specify export operation. Use -h for help or if you want export all textures use -a"
+                ),
+                ExportError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+                ExportError::UnmatchedVariants(unmatched) => {
+                    for (position, (pattern, suggestions)) in unmatched.iter().enumerate() {
+                        if position > 0 {
+                            writeln!(f)?;
+                        }
+                        match is_variant_pattern(pattern) {
+                            true => write!(f, "No variant matches {}", pattern)?,
+                            false => write_invalid_variant(f, pattern, suggestions)?,
+                        }
+                    }
+                    Ok(())
+                }
+                ExportError::InvalidVariantPattern((pattern, message)) => {
+                    write!(f, "Invalid variant pattern {}: {}", pattern, message)
+                }
+                ExportError::AmbiguousVariant((variant, paths)) => {
+                    write_ambiguous(f, variant, paths)
+                }
+                ExportError::MaterialIndexOutOfRange(index) => write_index_out_of_range(f, index),
+                ExportError::InvalidPaletteSize(size) => write_invalid_palette_size(f, size),
+                ExportError::VariantWithLut => write!(
+                    f,
+                    "--variant can't be used with --layout lut, the lookup texture contains all variants"
+                ),
+                ExportError::ImageWrite(paths) => {
+                    write!(f, "Failed to save {} file(s):", paths.len())?;
+                    for path in paths {
+                        write!(f, "\n  {}", path.to_string_lossy())?;
+                    }
+                    Ok(())
+                }
+                ExportError::FilenameWithDirectory => write!(
+                    f,
+                    "--filename can't be used when exporting a directory, every file keeps its own name"
+                ),
+                ExportError::InvalidNameTemplate((template, e)) => {
+                    write!(f, "Invalid name template {}: ", template)?;
+                    match e {
+                        NameTemplateError::UnknownPlaceholder(placeholder) => write!(
+                            f,
+                            "unknown placeholder {{{}}}, use one of {{{}}}",
+                            placeholder,
+                            NAME_PLACEHOLDERS.join("}, {")
+                        ),
+                        NameTemplateError::UnclosedPlaceholder => {
+                            write!(f, "{{ without closing }}")
+                        }
+                        NameTemplateError::OutsideOutputDirectory => write!(
+                            f,
+                            "names must be relative to the output directory, without .."
+                        ),
+                    }
+                }
+                ExportError::DuplicateOutputName(name) => write!(
+                    f,
+                    "Name template gives more than one file the name {}, add {{property}} or {{variant}}",
+                    name
+                ),
+                ExportError::InvalidGlob((glob, message)) => {
+                    write!(f, "Invalid glob {}: {}", glob, message)
+                }
+                ExportError::DirectoryRead(path) => {
+                    write!(f, "Failed to read directory {}", path.to_string_lossy())
+                }
+                ExportError::NoMetaFiles(path) => write!(
+                    f,
+                    "No .meta files matching --include and --exclude found in {}",
+                    path.to_string_lossy()
+                ),
+                ExportError::FilesFailed((failed_files, total)) => {
+                    write!(f, "Failed to export {} of {} files:", failed_files.len(), total)?;
+                    for (path, _) in failed_files {
+                        write!(f, "\n  {}", path.to_string_lossy())?;
+                    }
+                    Ok(())
+                }
+                ExportError::Config(e) => write!(f, "{}", e),
+                ExportError::WatchWithJson => write!(
+                    f,
+                    "--watch can't be used with --output json, the JSON document is printed only when the command ends"
+                ),
+                ExportError::Watch((path, message)) => {
+                    write!(f, "Failed to watch {}: {}", path.to_string_lossy(), message)
+                }
+            },
+            CliError::Dedupe(e) => match e {
+                DedupeError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                DedupeError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+            },
+            CliError::Variant(e) => match e {
+                VariantError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                VariantError::FileWrite => write!(f, "{}", FILE_WRITE_MESSAGE),
+                VariantError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+                VariantError::InvalidVariantName((variant, suggestions)) => {
+                    write_invalid_variant(f, variant, suggestions)
+                }
+                VariantError::AmbiguousVariant((variant, paths)) => {
+                    write_ambiguous(f, variant, paths)
+                }
+                VariantError::VariantAlreadyExist(variant) => write!(
+                    f,
+                    "Variant {} already exists in target. Use --replace to overwrite it",
+                    variant
+                ),
+                VariantError::EmptySnippet => {
+                    write!(f, "Snippet file does not contain any variant")
+                }
+                VariantError::InvalidMaterialIndexes(indexes) => {
+                    write!(f, "Snippet overrides materials missing in target:")?;
+                    for (variant_path, index) in indexes {
+                        write!(f, "\n  {}: {}", format_variant_path(variant_path), index)?;
+                    }
+                    Ok(())
+                }
+            },
+            CliError::Variants(e) => match e {
+                VariantsError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                VariantsError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+            },
+            CliError::Inspect(e) => match e {
+                InspectError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                InspectError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+                InspectError::InvalidVariantName((variant, suggestions)) => {
+                    write_invalid_variant(f, variant, suggestions)
+                }
+                InspectError::AmbiguousVariant((variant, paths)) => {
+                    write_ambiguous(f, variant, paths)
+                }
+                InspectError::MaterialIndexOutOfRange(index) => write_index_out_of_range(f, index),
+                InspectError::InvalidPaletteSize(size) => write_invalid_palette_size(f, size),
+            },
+            CliError::Explain(e) => match e {
+                ExplainError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                ExplainError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+                ExplainError::InvalidVariantName((variant, suggestions)) => {
+                    write_invalid_variant(f, variant, suggestions)
+                }
+                ExplainError::AmbiguousVariant((variant, paths)) => {
+                    write_ambiguous(f, variant, paths)
+                }
+                ExplainError::MaterialIndexOutOfRange(index) => write_index_out_of_range(f, index),
+                ExplainError::IndexNotInPalette((index, size)) => write!(
+                    f,
+                    "Material {} does not exist, palette has {} materials",
+                    index, size
+                ),
+            },
+            CliError::Edit(e) => match e {
+                EditError::FileRead => write!(f, "{}", FILE_READ_MESSAGE),
+                EditError::JsonParse(diagnostic) => write!(f, "{}", diagnostic.trim_end()),
+                EditError::InvalidVariantName((variant, suggestions)) => {
+                    write_invalid_variant(f, variant, suggestions)
+                }
+                EditError::AmbiguousVariant((variant, paths)) => write_ambiguous(f, variant, paths),
+                EditError::EmptyPalette => write!(f, "The palette has no materials to edit"),
+                EditError::Terminal => write!(
+                    f,
+                    "The editor needs an interactive terminal on standard input and output"
+                ),
+            },
+            CliError::Validate(e) => match e {
+                ValidateError::Config(e) => write!(f, "{}", e),
+                ValidateError::ReportWrite => write!(f, "{}", REPORT_WRITE_MESSAGE),
+                ValidateError::ValidationFailed(failed_files) => {
+                    write!(f, "Validation failed for {} file(s)", failed_files)
+                }
+            },
+            CliError::Lint(e) => match e {
+                LintError::Config(e) => write!(f, "{}", e),
+                LintError::ReportWrite => write!(f, "{}", REPORT_WRITE_MESSAGE),
+                LintError::LintFailed(failed_files) => {
+                    write!(f, "Lint failed for {} file(s)", failed_files)
+                }
+            },
+            CliError::Schema(e) => match e {
+                SchemaError::FileWrite => write!(f, "{}", FILE_WRITE_MESSAGE),
+            },
+            CliError::Config(e) => match e {
+                ConfigCommandError::Config(e) => write!(f, "{}", e),
+                ConfigCommandError::Serialize => {
+                    write!(f, "Failed to serialize the configuration")
+                }
+            },
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Export(e) => match e {
+                ExportError::FileRead | ExportError::DirectoryRead(_) => EXIT_INPUT_READ,
+                ExportError::NoOperations
+                | ExportError::VariantWithLut
+                | ExportError::FilenameWithDirectory
+                | ExportError::InvalidNameTemplate(_)
+                | ExportError::DuplicateOutputName(_)
+                | ExportError::InvalidGlob(_)
+                | ExportError::NoMetaFiles(_)
+                | ExportError::UnmatchedVariants(_)
+                | ExportError::InvalidVariantPattern(_)
+                | ExportError::AmbiguousVariant(_)
+                | ExportError::WatchWithJson => EXIT_USAGE,
+                ExportError::Watch(_) => EXIT_INPUT_READ,
+                ExportError::Config(_) => EXIT_CONFIG,
+                ExportError::JsonParse(_)
+                | ExportError::MaterialIndexOutOfRange(_)
+                | ExportError::InvalidPaletteSize(_) => EXIT_INVALID_INPUT,
+                ExportError::ImageWrite(_) => EXIT_OUTPUT_WRITE,
+                // Exit code of the first failed file
+                ExportError::FilesFailed((failed_files, _)) => failed_files
+                    .first()
+                    .map_or(EXIT_OUTPUT_WRITE, |(_, exit_code)| *exit_code),
+            },
+            CliError::Dedupe(e) => match e {
+                DedupeError::FileRead => EXIT_INPUT_READ,
+                DedupeError::JsonParse(_) => EXIT_INVALID_INPUT,
+            },
+            CliError::Variant(e) => match e {
+                VariantError::FileRead => EXIT_INPUT_READ,
+                VariantError::FileWrite => EXIT_OUTPUT_WRITE,
+                VariantError::JsonParse(_)
+                | VariantError::EmptySnippet
+                | VariantError::InvalidMaterialIndexes(_) => EXIT_INVALID_INPUT,
+                VariantError::InvalidVariantName(_)
+                | VariantError::AmbiguousVariant(_)
+                | VariantError::VariantAlreadyExist(_) => EXIT_USAGE,
+            },
+            CliError::Variants(e) => match e {
+                VariantsError::FileRead => EXIT_INPUT_READ,
+                VariantsError::JsonParse(_) => EXIT_INVALID_INPUT,
+            },
+            CliError::Inspect(e) => match e {
+                InspectError::FileRead => EXIT_INPUT_READ,
+                InspectError::InvalidVariantName(_) | InspectError::AmbiguousVariant(_) => {
+                    EXIT_USAGE
+                }
+                InspectError::JsonParse(_)
+                | InspectError::MaterialIndexOutOfRange(_)
+                | InspectError::InvalidPaletteSize(_) => EXIT_INVALID_INPUT,
+            },
+            CliError::Explain(e) => match e {
+                ExplainError::FileRead => EXIT_INPUT_READ,
+                ExplainError::InvalidVariantName(_)
+                | ExplainError::AmbiguousVariant(_)
+                | ExplainError::IndexNotInPalette(_) => EXIT_USAGE,
+                ExplainError::JsonParse(_) | ExplainError::MaterialIndexOutOfRange(_) => {
+                    EXIT_INVALID_INPUT
+                }
+            },
+            CliError::Edit(e) => match e {
+                EditError::FileRead => EXIT_INPUT_READ,
+                EditError::InvalidVariantName(_)
+                | EditError::AmbiguousVariant(_)
+                | EditError::Terminal => EXIT_USAGE,
+                EditError::JsonParse(_) | EditError::EmptyPalette => EXIT_INVALID_INPUT,
+            },
+            CliError::Validate(e) => match e {
+                ValidateError::Config(_) => EXIT_CONFIG,
+                ValidateError::ReportWrite => EXIT_OUTPUT_WRITE,
+                ValidateError::ValidationFailed(_) => EXIT_CHECK_FAILED,
+            },
+            CliError::Lint(e) => match e {
+                LintError::Config(_) => EXIT_CONFIG,
+                LintError::ReportWrite => EXIT_OUTPUT_WRITE,
+                LintError::LintFailed(_) => EXIT_CHECK_FAILED,
+            },
+            CliError::Schema(e) => match e {
+                SchemaError::FileWrite => EXIT_OUTPUT_WRITE,
+            },
+            CliError::Config(e) => match e {
+                ConfigCommandError::Config(_) | ConfigCommandError::Serialize => EXIT_CONFIG,
+            },
+        }
+    }
+}
+
+impl From<&CliError> for ExitCode {
+    fn from(e: &CliError) -> Self {
+        ExitCode::from(e.exit_code())
+    }
+}
@@ -0,0 +1,559 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{
+    diagnostic::{RuleLevel, RuleLevels, WARNINGS},
+    get_colors_from_meta::MaterialProperty,
+    lenient_parse::LENIENT_REPAIR,
+    lint::{LintSettings, LINT_RULES},
+    validate::VALIDATION_RULES,
+};
+
+pub const CONFIG_FILE_NAME: &str = "vt-utils.toml";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    FileRead(PathBuf),
+    Parse((PathBuf, String)),
+    UnknownRule((PathBuf, String)),
+    InvalidGlob((PathBuf, String)),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::FileRead(path) => {
+                write!(
+                    f,
+                    "Failed to read the config file {}",
+                    path.to_string_lossy()
+                )
+            }
+            ConfigError::Parse((path, message)) => {
+                write!(
+                    f,
+                    "Invalid config file {}: {}",
+                    path.to_string_lossy(),
+                    message
+                )
+            }
+            ConfigError::UnknownRule((path, rule)) => write!(
+                f,
+                "Unknown rule in config file {}: {}",
+                path.to_string_lossy(),
+                rule
+            ),
+            ConfigError::InvalidGlob((path, message)) => write!(
+                f,
+                "Invalid path glob in config file {}: {}",
+                path.to_string_lossy(),
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Lint thresholds, unset values keep the previous value.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct LintConfig {
+    pub similar_colors_delta_e: Option<f64>,
+    pub dark_emission_lightness: Option<f64>,
+}
+
+impl LintConfig {
+    fn merge(&mut self, other: &LintConfig) {
+        if other.similar_colors_delta_e.is_some() {
+            self.similar_colors_delta_e = other.similar_colors_delta_e;
+        }
+        if other.dark_emission_lightness.is_some() {
+            self.dark_emission_lightness = other.dark_emission_lightness;
+        }
+    }
+
+    pub fn to_settings(&self) -> LintSettings {
+        let defaults = LintSettings::default();
+        LintSettings {
+            similar_colors_delta_e: self
+                .similar_colors_delta_e
+                .unwrap_or(defaults.similar_colors_delta_e),
+            dark_emission_lightness: self
+                .dark_emission_lightness
+                .unwrap_or(defaults.dark_emission_lightness),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportLayout {
+    Grid,
+    Lut,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportImageFormat {
+    Png,
+    Tga,
+    Bmp,
+}
+
+/// Defaults of `export`, flags given on the command line take precedence.
+/// Unset values keep the previous value.
+#[derive(Deserialize, Serialize, Debug, Default, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExportConfig {
+    /// Relative to the directory of the config file
+    pub output_directory: Option<PathBuf>,
+    pub properties: Option<Vec<MaterialProperty>>,
+    pub all: Option<bool>,
+    pub variant: Option<Vec<String>>,
+    pub ignore_case: Option<bool>,
+    pub filename: Option<String>,
+    pub name_template: Option<String>,
+    pub layout: Option<ExportLayout>,
+    pub image_format: Option<ExportImageFormat>,
+    pub lenient: Option<bool>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    pub verbose: Option<bool>,
+    pub prune: Option<bool>,
+    pub jobs: Option<u16>,
+}
+
+impl ExportConfig {
+    /// Values used when neither the config file nor a flag sets them.
+    pub fn built_in() -> Self {
+        ExportConfig {
+            output_directory: Some(PathBuf::from(".")),
+            properties: Some(Vec::new()),
+            all: Some(false),
+            variant: Some(Vec::new()),
+            ignore_case: Some(false),
+            filename: None,
+            name_template: None,
+            layout: Some(ExportLayout::Grid),
+            image_format: Some(ExportImageFormat::Png),
+            lenient: Some(false),
+            include: Some(Vec::new()),
+            exclude: Some(Vec::new()),
+            verbose: Some(false),
+            prune: Some(false),
+            jobs: None,
+        }
+    }
+
+    pub fn merge(&mut self, other: &ExportConfig) {
+        let other = other.clone();
+        self.output_directory = other.output_directory.or(self.output_directory.take());
+        self.properties = other.properties.or(self.properties.take());
+        self.all = other.all.or(self.all);
+        self.variant = other.variant.or(self.variant.take());
+        self.ignore_case = other.ignore_case.or(self.ignore_case);
+        self.filename = other.filename.or(self.filename.take());
+        self.name_template = other.name_template.or(self.name_template.take());
+        self.layout = other.layout.or(self.layout);
+        self.image_format = other.image_format.or(self.image_format);
+        self.lenient = other.lenient.or(self.lenient);
+        self.include = other.include.or(self.include.take());
+        self.exclude = other.exclude.or(self.exclude.take());
+        self.verbose = other.verbose.or(self.verbose);
+        self.prune = other.prune.or(self.prune);
+        self.jobs = other.jobs.or(self.jobs);
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigOverride {
+    /// Globs relative to the directory of the config file
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub rules: RuleLevels,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(skip)]
+    matcher: GlobSet,
+}
+
+/// Contents of `vt-utils.toml`. Overrides are applied in file order on top
+/// of the top level settings when the checked file matches any of their paths.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub rules: RuleLevels,
+    #[serde(default)]
+    pub lint: LintConfig,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub overrides: Vec<ConfigOverride>,
+    #[serde(skip)]
+    root: PathBuf,
+    /// File the config was read from, `None` without config file
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// Settings resolved for a single file.
+#[derive(Debug, Default, PartialEq)]
+pub struct FileConfig {
+    pub rules: RuleLevels,
+    pub lint: LintConfig,
+}
+
+/// Config file merged with built-in defaults, as printed by `config show`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct EffectiveConfig {
+    pub export: ExportConfig,
+    /// Sorted so the output is stable
+    pub rules: BTreeMap<String, RuleLevel>,
+    pub lint: LintConfig,
+}
+
+fn is_known_rule(rule: &str) -> bool {
+    rule == WARNINGS
+        || rule == LENIENT_REPAIR
+        || VALIDATION_RULES.contains(&rule)
+        || LINT_RULES.iter().any(|lint_rule| lint_rule.id == rule)
+}
+
+fn check_rules(rules: &RuleLevels, config_path: &Path) -> Result<(), ConfigError> {
+    match rules.keys().find(|rule| !is_known_rule(rule)) {
+        Some(rule) => Err(ConfigError::UnknownRule((
+            config_path.to_path_buf(),
+            rule.to_owned(),
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn build_matcher(patterns: &[String], config_path: &Path) -> Result<GlobSet, ConfigError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| ConfigError::InvalidGlob((config_path.to_path_buf(), e.to_string())))?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| ConfigError::InvalidGlob((config_path.to_path_buf(), e.to_string())))
+}
+
+pub fn parse_config(content: &str, config_path: &Path) -> Result<ProjectConfig, ConfigError> {
+    let mut config: ProjectConfig = toml::from_str(content)
+        .map_err(|e| ConfigError::Parse((config_path.to_path_buf(), e.to_string())))?;
+
+    check_rules(&config.rules, config_path)?;
+    for config_override in config.overrides.iter_mut() {
+        check_rules(&config_override.rules, config_path)?;
+        config_override.matcher = build_matcher(&config_override.paths, config_path)?;
+    }
+    config.root = match config_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    config.export.output_directory = config
+        .export
+        .output_directory
+        .map(|directory| config.root.join(directory));
+    config.path = Some(config_path.to_path_buf());
+    Ok(config)
+}
+
+pub fn read_config(config_path: &Path) -> Result<ProjectConfig, ConfigError> {
+    let content = fs::read_to_string(config_path)
+        .map_err(|_| ConfigError::FileRead(config_path.to_path_buf()))?;
+    parse_config(&content, config_path)
+}
+
+/// Looks for `vt-utils.toml` in `start` and its parent directories.
+pub fn find_config_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|directory| directory.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The given config file, or the one found from the directory of `input`
+/// upwards.
+fn config_file_for(config_path: &Option<PathBuf>, input: &Path) -> Option<PathBuf> {
+    match config_path {
+        Some(path) => Some(path.to_owned()),
+        None => {
+            let input = input.canonicalize().unwrap_or_else(|_| input.to_path_buf());
+            let start = match input.is_dir() {
+                true => input.as_path(),
+                false => input.parent().unwrap_or(Path::new(".")),
+            };
+            find_config_file(start)
+        }
+    }
+}
+
+/// Reads the given config file, or the one found from the directory of
+/// `input` upwards. Without any config file all rules keep their default level.
+pub fn load_project_config_for(
+    config_path: &Option<PathBuf>,
+    input: &Path,
+) -> Result<ProjectConfig, ConfigError> {
+    match config_file_for(config_path, input) {
+        Some(path) => read_config(&path),
+        None => Ok(ProjectConfig::default()),
+    }
+}
+
+/// Config of every input in the same order, see [`load_project_config_for`].
+/// Inputs sharing a config file share one parsed config.
+pub fn load_project_configs(
+    config_path: &Option<PathBuf>,
+    inputs: &[PathBuf],
+) -> Result<Vec<Arc<ProjectConfig>>, ConfigError> {
+    let mut loaded: HashMap<Option<PathBuf>, Arc<ProjectConfig>> = HashMap::new();
+    let mut configs = Vec::with_capacity(inputs.len());
+    for input in inputs.iter() {
+        let config_file = config_file_for(config_path, input);
+        let config = match loaded.get(&config_file) {
+            Some(config) => config.clone(),
+            None => {
+                let config = Arc::new(match &config_file {
+                    Some(path) => read_config(path)?,
+                    None => ProjectConfig::default(),
+                });
+                loaded.insert(config_file, config.clone());
+                config
+            }
+        };
+        configs.push(config);
+    }
+    Ok(configs)
+}
+
+/// Rule levels given on the command line, applied after the config file.
+pub fn rule_levels_from_flags(allow: &[String], warn: &[String], deny: &[String]) -> RuleLevels {
+    let mut levels = RuleLevels::new();
+    for (rules, level) in [
+        (allow, RuleLevel::Allow),
+        (warn, RuleLevel::Warn),
+        (deny, RuleLevel::Deny),
+    ] {
+        for rule in rules {
+            levels.insert(rule.to_owned(), level);
+        }
+    }
+    levels
+}
+
+fn relative_to(root: &Path, file_path: &Path) -> PathBuf {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let file_path = file_path
+        .canonicalize()
+        .unwrap_or_else(|_| file_path.to_path_buf());
+    file_path
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .unwrap_or(file_path)
+}
+
+impl ProjectConfig {
+    /// Directory of the config file, or the current directory without config
+    /// file.
+    pub fn root_directory(&self) -> PathBuf {
+        let root = match self.path {
+            Some(_) => self.root.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        root.canonicalize().unwrap_or(root)
+    }
+
+    pub fn for_file(&self, file_path: &Path) -> FileConfig {
+        let relative_path = relative_to(&self.root, file_path);
+        let mut file_config = FileConfig {
+            rules: self.rules.clone(),
+            lint: self.lint.clone(),
+        };
+
+        for config_override in self.overrides.iter() {
+            if config_override.matcher.is_match(&relative_path) {
+                file_config.rules.extend(config_override.rules.clone());
+                file_config.lint.merge(&config_override.lint);
+            }
+        }
+        file_config
+    }
+
+    /// Every setting used for `file_path`, including values not set in the file.
+    pub fn effective_for(&self, file_path: &Path) -> EffectiveConfig {
+        let file_config = self.for_file(file_path);
+        let mut export = ExportConfig::built_in();
+        export.merge(&self.export);
+        let settings = file_config.lint.to_settings();
+        EffectiveConfig {
+            export,
+            rules: file_config.rules.into_iter().collect(),
+            lint: LintConfig {
+                similar_colors_delta_e: Some(settings.similar_colors_delta_e),
+                dark_emission_lightness: Some(settings.dark_emission_lightness),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        lint::{DARK_EMISSION, SIMILAR_COLORS},
+        validate::EMPTY_VARIANT,
+    };
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        r#"
+        [rules]
+        warnings = "deny"
+        empty-variant = "allow"
+
+        [lint]
+        similar-colors-delta-e = 1.5
+
+        [export]
+        output-directory = "textures"
+        properties = ["color", "company-tint"]
+        layout = "lut"
+        image-format = "tga"
+
+        [[overrides]]
+        paths = ["vehicles/**/*.meta"]
+        rules = { dark-emission = "allow", empty-variant = "warn" }
+        lint = { dark-emission-lightness = 30.0 }
+
+        [[overrides]]
+        paths = ["vehicles/trains/*.meta"]
+        lint = { similar-colors-delta-e = 3.0 }
+        "#
+        .to_string()
+    }
+
+    #[test]
+    fn test_project_config_for_file() {
+        let config = parse_config(&get_test_data(), Path::new("mod/vt-utils.toml"))
+            .expect("Should parse config");
+
+        let base = config.for_file(Path::new("mod/buildings/house.obj.meta"));
+        assert_eq!(base.rules[WARNINGS], RuleLevel::Deny);
+        assert_eq!(base.rules[EMPTY_VARIANT], RuleLevel::Allow);
+        assert_eq!(base.lint.to_settings().similar_colors_delta_e, 1.5);
+        assert_eq!(base.lint.to_settings().dark_emission_lightness, 20.0);
+
+        let train = config.for_file(Path::new("mod/vehicles/trains/loco.obj.meta"));
+        assert_eq!(train.rules[DARK_EMISSION], RuleLevel::Allow);
+        assert_eq!(train.rules[EMPTY_VARIANT], RuleLevel::Warn);
+        assert_eq!(train.lint.similar_colors_delta_e, Some(3.0));
+        assert_eq!(train.lint.dark_emission_lightness, Some(30.0));
+
+        let truck = config.for_file(Path::new("mod/vehicles/trucks/big/truck.obj.meta"));
+        assert_eq!(truck.lint.similar_colors_delta_e, Some(1.5));
+        assert!(!truck.rules.contains_key(SIMILAR_COLORS));
+    }
+
+    #[test]
+    fn test_project_config_effective_for() {
+        let config = parse_config(&get_test_data(), Path::new("mod/vt-utils.toml"))
+            .expect("Should parse config");
+
+        let effective = config.effective_for(Path::new("mod/vehicles/trains/loco.obj.meta"));
+        assert_eq!(
+            effective.export.output_directory,
+            Some(PathBuf::from("mod/textures"))
+        );
+        assert_eq!(
+            effective.export.properties,
+            Some(vec![MaterialProperty::Color, MaterialProperty::CompanyTint])
+        );
+        assert_eq!(effective.export.layout, Some(ExportLayout::Lut));
+        assert_eq!(effective.export.image_format, Some(ExportImageFormat::Tga));
+        assert_eq!(effective.export.lenient, Some(false));
+        assert_eq!(effective.lint.similar_colors_delta_e, Some(3.0));
+        assert_eq!(effective.lint.dark_emission_lightness, Some(30.0));
+        assert_eq!(
+            effective.rules.keys().collect::<Vec<_>>(),
+            vec![DARK_EMISSION, EMPTY_VARIANT, WARNINGS]
+        );
+
+        let mut export = ExportConfig::built_in();
+        export.merge(&ExportConfig {
+            lenient: Some(true),
+            ..ExportConfig::default()
+        });
+        assert_eq!(export.lenient, Some(true));
+        assert_eq!(export.layout, Some(ExportLayout::Grid));
+    }
+
+    #[test]
+    fn test_load_project_configs_per_input() {
+        let root = std::env::temp_dir().join("vt-utils-test-load-project-configs");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("first/models")).expect("Should create directory");
+        fs::create_dir_all(root.join("second")).expect("Should create directory");
+        fs::write(
+            root.join("first/vt-utils.toml"),
+            "[rules]\nwarnings = \"deny\"",
+        )
+        .expect("Should write file");
+        fs::write(
+            root.join("second/vt-utils.toml"),
+            "[rules]\nwarnings = \"allow\"",
+        )
+        .expect("Should write file");
+
+        let inputs = [
+            root.join("first/models/a.obj.meta"),
+            root.join("second/b.obj.meta"),
+            root.join("first/c.obj.meta"),
+        ];
+        let configs = load_project_configs(&None, &inputs);
+        let _ = fs::remove_dir_all(&root);
+        let configs = configs.expect("Should load configs");
+        let levels: Vec<_> = configs
+            .iter()
+            .map(|config| config.rules[WARNINGS])
+            .collect();
+        assert_eq!(
+            levels,
+            vec![RuleLevel::Deny, RuleLevel::Allow, RuleLevel::Deny]
+        );
+        assert!(Arc::ptr_eq(&configs[0], &configs[2]));
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        let path = Path::new("vt-utils.toml");
+
+        let unknown_rule = parse_config("[rules]\nsimilar-colours = \"deny\"", path);
+        assert!(
+            matches!(unknown_rule, Err(ConfigError::UnknownRule((_, rule))) if rule == "similar-colours")
+        );
+
+        let unknown_level = parse_config("[rules]\nsimilar-colors = \"error\"", path);
+        assert!(matches!(unknown_level, Err(ConfigError::Parse(_))));
+
+        let invalid_glob = parse_config("[[overrides]]\npaths = [\"a/[\"]", path);
+        assert!(matches!(invalid_glob, Err(ConfigError::InvalidGlob(_))));
+
+        let unknown_property = parse_config("[export]\nproperties = [\"colour\"]", path);
+        assert!(matches!(unknown_property, Err(ConfigError::Parse(_))));
+    }
+}
@@ -0,0 +1,165 @@
+use super::{
+    hex_to_rgb::hex_to_rgb,
+    json_parse::{MaterialSchema, VTMetaSchema},
+    palette::{get_palette_from_variant_path, VTPalette},
+    variants::{get_variant_paths_from_meta, VariantPath},
+};
+
+#[derive(Debug)]
+pub struct DuplicateGroup {
+    pub indices: Vec<usize>,
+    pub diverging_variants: Vec<VariantPath>,
+}
+
+impl DuplicateGroup {
+    pub fn is_mergeable(&self) -> bool {
+        self.diverging_variants.is_empty()
+    }
+}
+
+fn material_channels(material: &MaterialSchema) -> Option<[u8; 8]> {
+    let [r, g, b] = hex_to_rgb(&material.color).ok()?;
+    Some([
+        r,
+        g,
+        b,
+        material.company_tint,
+        material.emission,
+        material.glassiness,
+        material.smoothness,
+        material.specular,
+    ])
+}
+
+/// Compares two materials channel by channel. Materials are considered equal
+/// when no channel differs by more than `tolerance`.
+pub fn materials_match(a: &MaterialSchema, b: &MaterialSchema, tolerance: u8) -> bool {
+    match (material_channels(a), material_channels(b)) {
+        (Some(a), Some(b)) => a
+            .iter()
+            .zip(b.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance),
+        _ => false,
+    }
+}
+
+fn group_palette(palette: &VTPalette, tolerance: u8) -> Vec<Vec<usize>> {
+    let mut assigned = vec![false; palette.len()];
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for first in 0..palette.len() {
+        if assigned[first] {
+            continue;
+        }
+        let mut group = vec![first];
+        for other in (first + 1)..palette.len() {
+            if !assigned[other] && materials_match(&palette[first], &palette[other], tolerance) {
+                assigned[other] = true;
+                group.push(other);
+            }
+        }
+        if group.len() > 1 {
+            groups.push(group);
+        }
+    }
+
+    groups
+}
+
+fn group_matches_in_palette(palette: &VTPalette, indices: &[usize], tolerance: u8) -> bool {
+    let first = &palette[indices[0]];
+    indices[1..]
+        .iter()
+        .all(|index| materials_match(first, &palette[*index], tolerance))
+}
+
+pub fn find_duplicate_materials(meta: &VTMetaSchema, tolerance: u8) -> Vec<DuplicateGroup> {
+    let variant_palettes: Vec<(VariantPath, VTPalette)> = get_variant_paths_from_meta(meta)
+        .into_iter()
+        .filter_map(|path| {
+            get_palette_from_variant_path(meta, &path)
+                .ok()
+                .map(|palette| (path, palette))
+        })
+        .collect();
+
+    group_palette(&meta.materials, tolerance)
+        .into_iter()
+        .map(|indices| {
+            let diverging_variants = variant_palettes
+                .iter()
+                .filter(|(_, palette)| !group_matches_in_palette(palette, &indices, tolerance))
+                .map(|(path, _)| path.clone())
+                .collect();
+            DuplicateGroup {
+                indices,
+                diverging_variants,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::parse_material_json;
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        r#"
+        {
+          "Materials": [
+            {"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffa500","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffa502","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "00ff00","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "00ff00","CompanyTint": 0,"Emission": 255,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+          ],
+          "Variants": {
+            "Foo": {
+              "Materials": {
+                "2": {"Color": "ff0000","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+              },
+              "Variants": null
+            }
+          }
+        }"#
+        .to_string()
+    }
+
+    #[test]
+    fn test_materials_match() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+
+        assert!(materials_match(&meta.materials[0], &meta.materials[2], 0));
+        assert!(!materials_match(&meta.materials[1], &meta.materials[3], 0));
+        assert!(materials_match(&meta.materials[1], &meta.materials[3], 2));
+        assert!(!materials_match(
+            &meta.materials[4],
+            &meta.materials[5],
+            254
+        ));
+    }
+
+    #[test]
+    fn test_find_duplicate_materials_exact() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let groups = find_duplicate_materials(&meta, 0);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].indices, vec![0, 2]);
+        assert_eq!(groups[0].diverging_variants, vec![vec!["Foo".to_string()]]);
+        assert!(!groups[0].is_mergeable());
+    }
+
+    #[test]
+    fn test_find_duplicate_materials_with_tolerance() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let groups = find_duplicate_materials(&meta, 2);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[1].indices, vec![1, 3]);
+        assert!(groups[1].is_mergeable());
+    }
+}
@@ -0,0 +1,119 @@
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let display: &str = match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", display)
+    }
+}
+
+/// Location inside the .meta document, e.g. `["Variants", "foo", "Materials", "12"]`.
+pub type JsonPath = Vec<String>;
+
+pub fn format_json_path(path: &JsonPath) -> String {
+    if path.is_empty() {
+        return "<root>".to_string();
+    }
+    path.join(".")
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub path: JsonPath,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] {}: {}",
+            self.severity,
+            self.rule,
+            format_json_path(&self.path),
+            self.message
+        )
+    }
+}
+
+/// Shows the given 1-based line of `source` with a caret under `column`.
+pub fn render_source_snippet(source: &str, line: usize, column: usize) -> String {
+    let Some(source_line) = source.lines().nth(line.saturating_sub(1)) else {
+        return String::new();
+    };
+    let gutter = " ".repeat(line.to_string().len().max(2));
+    let caret_offset: String = source_line
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "{gutter} |\n{:>width$} | {}\n{gutter} | {}^\n",
+        line,
+        source_line,
+        caret_offset,
+        width = gutter.len()
+    )
+}
+
+/// Pseudo rule matching every warning, `warnings = deny` fails on any warning.
+pub const WARNINGS: &str = "warnings";
+
+/// Level of a rule set by the user. `Allow` suppresses the rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
+pub type RuleLevels = HashMap<String, RuleLevel>;
+
+pub fn apply_rule_levels(diagnostics: Vec<Diagnostic>, levels: &RuleLevels) -> Vec<Diagnostic> {
+    diagnostics
+        .into_iter()
+        .filter_map(|mut diagnostic| {
+            match levels.get(diagnostic.rule) {
+                Some(RuleLevel::Allow) => return None,
+                Some(RuleLevel::Warn) => diagnostic.severity = Severity::Warning,
+                Some(RuleLevel::Deny) => diagnostic.severity = Severity::Error,
+                None => {}
+            }
+            if diagnostic.severity == Severity::Warning
+                && levels.get(WARNINGS) == Some(&RuleLevel::Deny)
+            {
+                diagnostic.severity = Severity::Error;
+            }
+            Some(diagnostic)
+        })
+        .collect()
+}
+
+/// One line per diagnostic prefixed with the file path.
+pub fn format_diagnostics(file_path: &Path, diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|diagnostic| format!("{}: {}\n", file_path.to_string_lossy(), diagnostic))
+        .collect()
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
@@ -0,0 +1,343 @@
+use super::{
+    get_colors_from_meta::MaterialProperty,
+    hex_to_rgb::hex_to_rgb,
+    json_parse::{MaterialSchema, VTMetaSchema},
+    palette::{get_palette_from_variant_path, VTPalette},
+    variants::{
+        get_variant_by_path, get_variant_by_path_mut, get_variant_tree_from_meta, VariantNode,
+        VariantPath,
+    },
+};
+
+/// Row of the variant sidebar, the base palette has an empty path.
+#[derive(Debug, Clone)]
+pub struct VariantEntry {
+    pub depth: usize,
+    pub name: String,
+    pub path: VariantPath,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum EditValueError {
+    InvalidColor,
+    InvalidNumber,
+}
+
+fn collect_variant_entries(nodes: &[VariantNode], depth: usize, entries: &mut Vec<VariantEntry>) {
+    for node in nodes {
+        entries.push(VariantEntry {
+            depth,
+            name: node.name.to_owned(),
+            path: node.path.clone(),
+        });
+        collect_variant_entries(&node.children, depth + 1, entries);
+    }
+}
+
+/// Sets `property` of `material` from text: hex color with or without # for
+/// color, 0-255 for other properties.
+pub fn set_property_value(
+    material: &mut MaterialSchema,
+    property: &MaterialProperty,
+    value: &str,
+) -> Result<(), EditValueError> {
+    let value = value.trim();
+    if *property == MaterialProperty::Color {
+        let hex = value.trim_start_matches('#');
+        if hex.len() != 6 || hex_to_rgb(hex).is_err() {
+            return Err(EditValueError::InvalidColor);
+        }
+        material.color = hex.to_lowercase();
+        return Ok(());
+    }
+
+    let number: u8 = value.parse().map_err(|_| EditValueError::InvalidNumber)?;
+    match property {
+        MaterialProperty::Color => {}
+        MaterialProperty::CompanyTint => material.company_tint = number,
+        MaterialProperty::Emission => material.emission = number,
+        MaterialProperty::Glassiness => material.glassiness = number,
+        MaterialProperty::Smoothness => material.smoothness = number,
+        MaterialProperty::Specular => material.specular = number,
+    }
+    Ok(())
+}
+
+pub fn get_property_number(material: &MaterialSchema, property: &MaterialProperty) -> Option<u8> {
+    match property {
+        MaterialProperty::Color => None,
+        MaterialProperty::CompanyTint => Some(material.company_tint),
+        MaterialProperty::Emission => Some(material.emission),
+        MaterialProperty::Glassiness => Some(material.glassiness),
+        MaterialProperty::Smoothness => Some(material.smoothness),
+        MaterialProperty::Specular => Some(material.specular),
+    }
+}
+
+/// State of the interactive editor without any terminal handling. Every
+/// change stores a snapshot of the whole file for undo, files are small.
+pub struct PaletteEditor {
+    pub meta: VTMetaSchema,
+    pub variants: Vec<VariantEntry>,
+    pub selected_variant: usize,
+    pub selected_material: usize,
+    undo_stack: Vec<VTMetaSchema>,
+    redo_stack: Vec<VTMetaSchema>,
+    /// Undo depth matching the file on disk, `None` when that state was
+    /// dropped from the history
+    saved_depth: Option<usize>,
+}
+
+impl PaletteEditor {
+    pub fn new(meta: VTMetaSchema) -> Self {
+        let mut variants = vec![VariantEntry {
+            depth: 0,
+            name: "Materials".to_string(),
+            path: Vec::new(),
+        }];
+        collect_variant_entries(&get_variant_tree_from_meta(&meta), 1, &mut variants);
+        PaletteEditor {
+            meta,
+            variants,
+            selected_variant: 0,
+            selected_material: 0,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            saved_depth: Some(0),
+        }
+    }
+
+    pub fn variant_path(&self) -> &VariantPath {
+        &self.variants[self.selected_variant].path
+    }
+
+    /// Palette of the selected variant, the base palette when a variant
+    /// overrides materials missing in it.
+    pub fn palette(&self) -> VTPalette {
+        get_palette_from_variant_path(&self.meta, self.variant_path())
+            .unwrap_or_else(|_| self.meta.materials.clone())
+    }
+
+    /// Selected material, `None` when the palette is shorter than the selection.
+    pub fn material(&self) -> Option<MaterialSchema> {
+        self.palette().get(self.selected_material).cloned()
+    }
+
+    pub fn is_overridden(&self, index: usize) -> bool {
+        get_variant_by_path(&self.meta, self.variant_path())
+            .is_some_and(|variant| variant.materials.contains_key(&(index as u8)))
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.saved_depth != Some(self.undo_stack.len())
+    }
+
+    /// Writes the selected material. In a variant a material which is not
+    /// overridden yet gets a new override.
+    fn apply(&mut self, material: MaterialSchema) {
+        if self.material().is_none_or(|selected| selected == material) {
+            return;
+        }
+        if self
+            .saved_depth
+            .is_some_and(|depth| depth > self.undo_stack.len())
+        {
+            self.saved_depth = None;
+        }
+        self.undo_stack.push(self.meta.clone());
+        self.redo_stack.clear();
+
+        let index = self.selected_material;
+        let variant_path = self.variant_path().clone();
+        match get_variant_by_path_mut(&mut self.meta, &variant_path) {
+            Some(variant) => {
+                variant.materials.insert(index as u8, material);
+            }
+            None => self.meta.materials[index] = material,
+        }
+    }
+
+    pub fn set_value(
+        &mut self,
+        property: &MaterialProperty,
+        value: &str,
+    ) -> Result<(), EditValueError> {
+        let Some(mut material) = self.material() else {
+            return Ok(());
+        };
+        set_property_value(&mut material, property, value)?;
+        self.apply(material);
+        Ok(())
+    }
+
+    /// Moves a numeric property by `delta`, clamped to 0-255.
+    pub fn adjust(&mut self, property: &MaterialProperty, delta: i32) {
+        let Some(mut material) = self.material() else {
+            return;
+        };
+        if let Some(number) = get_property_number(&material, property) {
+            let number = (number as i32 + delta).clamp(0, u8::MAX as i32);
+            if set_property_value(&mut material, property, &number.to_string()).is_ok() {
+                self.apply(material);
+            }
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(meta) => {
+                self.redo_stack
+                    .push(std::mem::replace(&mut self.meta, meta));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(meta) => {
+                self.undo_stack
+                    .push(std::mem::replace(&mut self.meta, meta));
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.saved_depth = Some(self.undo_stack.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::parse_material_json;
+
+    use super::*;
+
+    fn get_test_meta() -> VTMetaSchema {
+        let material = r#"{"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}"#;
+        let base: Vec<&str> = (0..16).map(|_| material).collect();
+        let data = format!(
+            r#"{{
+              "Materials": [{}],
+              "Variants": {{
+                "foo": {{
+                  "Materials": {{ "1": {} }},
+                  "Variants": {{ "bar": {{ "Materials": {{}}, "Variants": null }} }}
+                }}
+              }}
+            }}"#,
+            base.join(","),
+            material
+        );
+        parse_material_json(&data).expect("Should create meta")
+    }
+
+    #[test]
+    fn test_palette_editor_variants() {
+        let editor = PaletteEditor::new(get_test_meta());
+        let entries: Vec<(usize, &str)> = editor
+            .variants
+            .iter()
+            .map(|entry| (entry.depth, entry.name.as_str()))
+            .collect();
+        assert_eq!(entries, vec![(0, "Materials"), (1, "foo"), (2, "bar")]);
+    }
+
+    #[test]
+    fn test_palette_editor_edits_base_and_variant() {
+        let mut editor = PaletteEditor::new(get_test_meta());
+        editor.selected_material = 3;
+        editor
+            .set_value(&MaterialProperty::Color, "#FF0000")
+            .expect("Should set color");
+        assert_eq!(editor.meta.materials[3].color, "ff0000");
+
+        editor.selected_variant = 2;
+        editor.adjust(&MaterialProperty::Emission, 300);
+        assert!(editor.is_overridden(3));
+        let bar = get_variant_by_path(&editor.meta, &vec!["foo".into(), "bar".into()])
+            .expect("Should find variant");
+        assert_eq!(bar.materials[&3].emission, 255);
+        assert_eq!(bar.materials[&3].color, "ff0000");
+        assert_eq!(editor.meta.materials[3].emission, 0);
+
+        assert_eq!(
+            editor.set_value(&MaterialProperty::Color, "12345"),
+            Err(EditValueError::InvalidColor)
+        );
+        assert_eq!(
+            editor.set_value(&MaterialProperty::Specular, "256"),
+            Err(EditValueError::InvalidNumber)
+        );
+    }
+
+    #[test]
+    fn test_palette_editor_selection_outside_palette() {
+        let mut editor = PaletteEditor::new(get_test_meta());
+        editor.selected_material = editor.meta.materials.len();
+        assert!(editor.material().is_none());
+        editor.adjust(&MaterialProperty::Emission, 10);
+        assert_eq!(editor.set_value(&MaterialProperty::Color, "ff0000"), Ok(()));
+        assert!(!editor.has_changes());
+    }
+
+    #[test]
+    fn test_palette_editor_undo_redo() {
+        let mut editor = PaletteEditor::new(get_test_meta());
+        editor.adjust(&MaterialProperty::Smoothness, 10);
+        editor.adjust(&MaterialProperty::Smoothness, 5);
+        editor.adjust(&MaterialProperty::Smoothness, -100);
+        assert_eq!(
+            editor.material().expect("Should get material").smoothness,
+            0
+        );
+        // Clamped value equal to the current one is not an edit
+        editor.adjust(&MaterialProperty::Smoothness, -1);
+
+        assert!(editor.undo());
+        assert_eq!(
+            editor.material().expect("Should get material").smoothness,
+            15
+        );
+        assert!(editor.undo());
+        assert_eq!(
+            editor.material().expect("Should get material").smoothness,
+            10
+        );
+        assert!(editor.redo());
+        assert_eq!(
+            editor.material().expect("Should get material").smoothness,
+            15
+        );
+
+        editor.adjust(&MaterialProperty::Smoothness, 1);
+        assert!(!editor.redo());
+        assert!(editor.undo());
+        assert!(editor.undo());
+        assert!(editor.undo());
+        assert!(!editor.undo());
+        assert!(!editor.has_changes());
+    }
+
+    #[test]
+    fn test_palette_editor_has_changes() {
+        let mut editor = PaletteEditor::new(get_test_meta());
+        editor.adjust(&MaterialProperty::Specular, 1);
+        editor.mark_saved();
+        assert!(!editor.has_changes());
+
+        editor.undo();
+        assert!(editor.has_changes());
+        editor.redo();
+        assert!(!editor.has_changes());
+
+        editor.undo();
+        editor.adjust(&MaterialProperty::Specular, 2);
+        assert!(editor.has_changes());
+        editor.undo();
+        assert!(editor.has_changes());
+    }
+}
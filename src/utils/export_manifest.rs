@@ -0,0 +1,268 @@
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    output::hash_file,
+    write_meta::{write_meta_file, WriteMetaError},
+};
+
+/// Manifest kept in the output directory of `export`
+pub const MANIFEST_FILE_NAME: &str = ".vt-utils-export.json";
+
+/// Version of the manifest layout, manifests of other versions are ignored
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Options selecting which textures are exported. Outputs are only stale
+/// when these are unchanged.
+const SELECTION_OPTIONS: [&str; 4] = ["properties", "layout", "variant", "ignore_case"];
+
+/// What was exported from one .meta file. Paths are relative to the output
+/// directory and `/` separated.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub input_sha256: String,
+    /// Export options affecting the written files
+    pub options: Value,
+    /// Written files with their SHA-256
+    pub outputs: IndexMap<String, String>,
+    /// Files written by an earlier export which is not produced anymore, e.g.
+    /// for a removed variant, kept until they are pruned
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stale: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ExportManifest {
+    pub version: u32,
+    /// Entries by input path, relative to the directory of vt-utils.toml or
+    /// the current directory without it
+    pub inputs: IndexMap<String, ManifestEntry>,
+}
+
+impl Default for ExportManifest {
+    fn default() -> Self {
+        ExportManifest {
+            version: MANIFEST_VERSION,
+            inputs: IndexMap::new(),
+        }
+    }
+}
+
+/// Path with `/` separators used for manifest keys and outputs.
+pub fn manifest_path(path: &Path) -> String {
+    path.components()
+        .map(|component| match component {
+            // Joined with the separator, an absolute path keeps its leading `/`
+            Component::RootDir => "".into(),
+            _ => component.as_os_str().to_string_lossy(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn file_sha256(path: &Path) -> Option<String> {
+    hash_file(path).map(|written_file| written_file.sha256)
+}
+
+/// Manifest of `output_directory`. A missing, unreadable or outdated manifest
+/// is empty, so everything gets exported again.
+pub fn read_manifest(output_directory: &Path) -> ExportManifest {
+    fs::read_to_string(output_directory.join(MANIFEST_FILE_NAME))
+        .ok()
+        .and_then(|content| serde_json::from_str::<ExportManifest>(&content).ok())
+        .filter(|manifest| manifest.version == MANIFEST_VERSION)
+        .unwrap_or_default()
+}
+
+pub fn write_manifest(
+    output_directory: &Path,
+    manifest: &ExportManifest,
+) -> Result<PathBuf, WriteMetaError> {
+    let path = output_directory.join(MANIFEST_FILE_NAME);
+    write_meta_file(&path, manifest)?;
+    Ok(path)
+}
+
+impl ManifestEntry {
+    pub fn new(
+        input_sha256: String,
+        options: Value,
+        output_directory: &Path,
+        written_files: &[PathBuf],
+    ) -> Self {
+        let outputs = written_files
+            .iter()
+            .filter_map(|path| {
+                let relative_path = path.strip_prefix(output_directory).ok()?;
+                Some((manifest_path(relative_path), file_sha256(path)?))
+            })
+            .collect();
+        ManifestEntry {
+            input_sha256,
+            options,
+            outputs,
+            stale: Vec::new(),
+        }
+    }
+
+    /// True when the input and options are the same as recorded and every
+    /// output still exists unchanged.
+    pub fn is_up_to_date(
+        &self,
+        input_sha256: &str,
+        options: &Value,
+        output_directory: &Path,
+    ) -> bool {
+        self.input_sha256 == input_sha256
+            && self.options == *options
+            && self.outputs.iter().all(|(path, sha256)| {
+                file_sha256(&output_directory.join(path)).as_ref() == Some(sha256)
+            })
+    }
+
+    /// Every file recorded by this entry which still exists.
+    pub fn existing_files(&self, output_directory: &Path) -> Vec<String> {
+        self.outputs
+            .keys()
+            .chain(self.stale.iter())
+            .filter(|path| output_directory.join(path).is_file())
+            .cloned()
+            .collect()
+    }
+
+    /// True when both exports select the same properties, variants and layout,
+    /// so they produce the same textures, maybe under other names.
+    pub fn selects_same_outputs(&self, other: &ManifestEntry) -> bool {
+        SELECTION_OPTIONS
+            .iter()
+            .all(|option| self.options.get(option) == other.options.get(option))
+    }
+
+    /// Files of `previous` which this export didn't write but which still exist.
+    /// Nothing is stale when the selection changed, e.g. an export of a single
+    /// variant after an export of every variant doesn't make the others stale.
+    pub fn stale_outputs(&self, previous: &ManifestEntry, output_directory: &Path) -> Vec<String> {
+        if !self.selects_same_outputs(previous) {
+            return Vec::new();
+        }
+        previous
+            .existing_files(output_directory)
+            .into_iter()
+            .filter(|path| !self.outputs.contains_key(path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_manifest_path() {
+        assert_eq!(
+            manifest_path(Path::new("vehicles/bus.obj.meta")),
+            "vehicles/bus.obj.meta"
+        );
+        #[cfg(unix)]
+        assert_eq!(
+            manifest_path(Path::new("/mods/bus.obj.meta")),
+            "/mods/bus.obj.meta"
+        );
+    }
+
+    #[test]
+    fn test_manifest_entry_up_to_date_and_stale() {
+        let directory = std::env::temp_dir().join("vt-utils-test-export-manifest");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("sub")).expect("Should create directory");
+        let written: Vec<PathBuf> = ["a-color.png", "sub/a-color-night.png"]
+            .iter()
+            .map(|name| directory.join(name))
+            .collect();
+        for path in written.iter() {
+            fs::write(path, "png").expect("Should write file");
+        }
+
+        let options = json!({ "properties": ["color"] });
+        let previous = ManifestEntry::new("abc".into(), options.clone(), &directory, &written);
+        assert_eq!(
+            previous.outputs.keys().collect::<Vec<_>>(),
+            vec!["a-color.png", "sub/a-color-night.png"]
+        );
+        assert!(previous.is_up_to_date("abc", &options, &directory));
+        assert!(!previous.is_up_to_date("abd", &options, &directory));
+        assert!(!previous.is_up_to_date("abc", &json!({}), &directory));
+
+        // Variant night was removed from the input
+        let current = ManifestEntry::new("abd".into(), options.clone(), &directory, &written[..1]);
+        assert_eq!(
+            current.stale_outputs(&previous, &directory),
+            vec!["sub/a-color-night.png"]
+        );
+
+        fs::write(&written[0], "changed").expect("Should write file");
+        assert!(!previous.is_up_to_date("abc", &options, &directory));
+        fs::remove_file(&written[1]).expect("Should remove file");
+        assert!(current.stale_outputs(&previous, &directory).is_empty());
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_manifest_entry_stale_with_changed_options() {
+        let directory = std::env::temp_dir().join("vt-utils-test-export-manifest-options");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).expect("Should create directory");
+        let written: Vec<PathBuf> = ["a-color.png", "a-emission.png", "a-color-deep.png"]
+            .iter()
+            .map(|name| directory.join(name))
+            .collect();
+        for path in written.iter() {
+            fs::write(path, "png").expect("Should write file");
+        }
+
+        let all = json!({ "properties": ["color", "emission"], "variant": [], "filename": null });
+        let previous = ManifestEntry::new("abc".into(), all.clone(), &directory, &written[..2]);
+
+        // Only the options changed, other textures are still wanted
+        let deep = json!({ "properties": ["color"], "variant": ["deep"], "filename": null });
+        let current = ManifestEntry::new("abc".into(), deep, &directory, &written[2..]);
+        assert!(current.stale_outputs(&previous, &directory).is_empty());
+
+        // Same selection under another name
+        let renamed =
+            json!({ "properties": ["color", "emission"], "variant": [], "filename": "b" });
+        let current = ManifestEntry::new("abc".into(), renamed, &directory, &written[2..]);
+        assert_eq!(
+            current.stale_outputs(&previous, &directory),
+            vec!["a-color.png", "a-emission.png"]
+        );
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_read_manifest_ignores_other_versions() {
+        let directory = std::env::temp_dir().join("vt-utils-test-read-manifest");
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).expect("Should create directory");
+
+        let mut manifest = ExportManifest::default();
+        manifest.inputs.insert(
+            "bus.obj.meta".into(),
+            ManifestEntry::new("abc".into(), json!({}), &directory, &[]),
+        );
+        write_manifest(&directory, &manifest).expect("Should write manifest");
+        assert_eq!(read_manifest(&directory).inputs.len(), 1);
+
+        manifest.version = MANIFEST_VERSION + 1;
+        write_manifest(&directory, &manifest).expect("Should write manifest");
+        assert!(read_manifest(&directory).inputs.is_empty());
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
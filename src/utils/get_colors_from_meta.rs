@@ -1,10 +1,13 @@
+use clap::ValueEnum;
 use core::fmt;
+use serde::{Deserialize, Serialize};
 
 use crate::utils::{hex_to_rgb::hex_to_rgb, save_image::Colors};
 
 use super::palette::VTPalette;
 
-#[derive(Hash, PartialEq, Eq, Debug)]
+#[derive(Hash, PartialEq, Eq, Debug, Clone, Copy, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
 pub enum MaterialProperty {
     Color,
     CompanyTint,
@@ -14,6 +17,15 @@ pub enum MaterialProperty {
     Specular,
 }
 
+pub const MATERIAL_PROPERTIES: [MaterialProperty; 6] = [
+    MaterialProperty::Color,
+    MaterialProperty::CompanyTint,
+    MaterialProperty::Emission,
+    MaterialProperty::Glassiness,
+    MaterialProperty::Smoothness,
+    MaterialProperty::Specular,
+];
+
 impl fmt::Display for MaterialProperty {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let display: &str = match self {
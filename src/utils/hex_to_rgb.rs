@@ -5,8 +5,8 @@ pub enum HexToRgbError {
 }
 pub fn hex_to_rgb(hex: &str) -> Result<[u8; 3], HexToRgbError> {
     let hex = hex.trim_start_matches('#');
-    match Vec::from_hex(hex) {
-        Ok(bytes) => Ok([bytes[0], bytes[1], bytes[2]]),
+    match <[u8; 3]>::from_hex(hex) {
+        Ok(bytes) => Ok(bytes),
         Err(_) => Err(HexToRgbError::ConversionError),
     }
 }
@@ -32,5 +32,13 @@ mod tests {
             hex_to_rgb("42424t"),
             Err(HexToRgbError::ConversionError)
         ));
+        assert!(matches!(
+            hex_to_rgb("42"),
+            Err(HexToRgbError::ConversionError)
+        ));
+        assert!(matches!(
+            hex_to_rgb("42424242"),
+            Err(HexToRgbError::ConversionError)
+        ));
     }
 }
@@ -0,0 +1,69 @@
+use rayon::{prelude::*, ThreadPoolBuilder};
+
+use super::output::Output;
+
+/// Runs `work` on a pool of `jobs` threads, one per CPU core when `None`.
+/// Parallel iterators inside `work` use this pool.
+pub fn run_with_jobs<T: Send>(jobs: Option<u16>, work: impl FnOnce() -> T + Send) -> T {
+    match ThreadPoolBuilder::new()
+        .num_threads(jobs.map_or(0, usize::from))
+        .build()
+    {
+        Ok(pool) => pool.install(work),
+        Err(_) => work(),
+    }
+}
+
+/// Maps `items` in parallel, every item with its own buffered output. Results
+/// are returned and outputs merged in the order of `items`, so printed text
+/// and JSON don't depend on which item finished first.
+pub fn map_ordered<I: Sync, T: Send>(
+    items: &[I],
+    output: &mut Output,
+    map: impl Fn(&I, &mut Output) -> T + Sync,
+) -> Vec<T> {
+    let template = output.buffered();
+    let mapped: Vec<(T, Output)> = items
+        .par_iter()
+        .map(|item| {
+            let mut item_output = template.buffered();
+            let value = map(item, &mut item_output);
+            (value, item_output)
+        })
+        .collect();
+
+    mapped
+        .into_iter()
+        .map(|(value, item_output)| {
+            output.merge(item_output);
+            value
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::output::OutputFormat;
+
+    use super::*;
+
+    #[test]
+    fn test_map_ordered_keeps_order() {
+        let items: Vec<u64> = (0..200).collect();
+        let mut output = Output::new(OutputFormat::Json);
+        let mapped = run_with_jobs(Some(4), || {
+            map_ordered(&items, &mut output, |item, item_output| {
+                // Uneven work so items finish out of order
+                std::thread::sleep(std::time::Duration::from_micros((200 - item) * 10));
+                item_output.set_result("last", item);
+                item * 2
+            })
+        });
+
+        assert_eq!(
+            mapped,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+        assert_eq!(output.to_json("test", None)["result"]["last"], 199);
+    }
+}
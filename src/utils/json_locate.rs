@@ -0,0 +1,269 @@
+use std::ops::Range;
+
+use super::diagnostic::JsonPath;
+
+struct Scanner {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+}
+
+struct Located {
+    depth: usize,
+    line: usize,
+    column: usize,
+    /// Character range of the value when the whole target path exists
+    value_span: Option<Range<usize>>,
+}
+
+fn record(path: &JsonPath, target: &JsonPath, line: usize, column: usize, best: &mut Located) {
+    if path.len() > best.depth && target.starts_with(path) {
+        best.depth = path.len();
+        best.line = line;
+        best.column = column;
+    }
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn advance(&mut self) {
+        if let Some(c) = self.peek() {
+            self.position += 1;
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+    }
+
+    /// Skips whitespace, byte order mark and commas. Commas are treated as
+    /// whitespace so trailing commas accepted by lenient parsing don't matter.
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if !(c.is_whitespace() || c == ',' || c == '\u{feff}') {
+                break;
+            }
+            self.advance();
+        }
+    }
+
+    fn scan_string(&mut self) -> String {
+        let mut value = String::new();
+        self.advance();
+        while let Some(c) = self.peek() {
+            self.advance();
+            match c {
+                '"' => break,
+                '\\' => {
+                    if let Some(escaped) = self.peek() {
+                        value.push(escaped);
+                        self.advance();
+                    }
+                }
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+
+    fn scan_value(&mut self, path: &mut JsonPath, target: &JsonPath, best: &mut Located) {
+        self.skip_separators();
+        let start = self.position;
+        match self.peek() {
+            Some('{') => {
+                self.advance();
+                loop {
+                    self.skip_separators();
+                    match self.peek() {
+                        None => break,
+                        Some('}') => {
+                            self.advance();
+                            break;
+                        }
+                        Some('"') => {
+                            let (line, column) = (self.line, self.column);
+                            let key = self.scan_string();
+                            self.skip_separators();
+                            if self.peek() == Some(':') {
+                                self.advance();
+                            }
+                            path.push(key);
+                            record(path, target, line, column, best);
+                            self.scan_value(path, target, best);
+                            path.pop();
+                        }
+                        Some(_) => self.advance(),
+                    }
+                }
+            }
+            Some('[') => {
+                self.advance();
+                let mut index = 0;
+                loop {
+                    self.skip_separators();
+                    match self.peek() {
+                        None => break,
+                        Some(']') => {
+                            self.advance();
+                            break;
+                        }
+                        Some(_) => {
+                            path.push(index.to_string());
+                            record(path, target, self.line, self.column, best);
+                            self.scan_value(path, target, best);
+                            path.pop();
+                            index += 1;
+                        }
+                    }
+                }
+            }
+            Some('"') => {
+                self.scan_string();
+            }
+            Some(_) => {
+                let start = self.position;
+                while let Some(c) = self.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | '}' | ']') {
+                        break;
+                    }
+                    self.advance();
+                }
+                // Skip a stray character so malformed input can't stall the scan
+                if self.position == start {
+                    self.advance();
+                }
+            }
+            None => {}
+        }
+        if path == target && best.value_span.is_none() {
+            best.value_span = Some(start..self.position);
+        }
+    }
+}
+
+fn scan(file_content: &str, path: &JsonPath) -> Located {
+    let mut scanner = Scanner {
+        chars: file_content.chars().collect(),
+        position: 0,
+        line: 1,
+        column: 1,
+    };
+    scanner.skip_separators();
+    let mut best = Located {
+        depth: 0,
+        line: scanner.line,
+        column: scanner.column,
+        value_span: None,
+    };
+    scanner.scan_value(&mut Vec::new(), path, &mut best);
+    best
+}
+
+/// Finds line and column (both 1-based) of `path` in the JSON source. Object
+/// members point at their key, array items at the item. When the path doesn't
+/// exist the location of its deepest existing parent is returned.
+pub fn locate_json_path(file_content: &str, path: &JsonPath) -> (usize, usize) {
+    let located = scan(file_content, path);
+    (located.line, located.column)
+}
+
+/// Byte range of the value at `path` in the JSON source, `None` when the
+/// path doesn't exist.
+pub fn locate_json_value(file_content: &str, path: &JsonPath) -> Option<Range<usize>> {
+    let span = scan(file_content, path).value_span?;
+    let byte_offset = |char_position: usize| {
+        file_content
+            .char_indices()
+            .nth(char_position)
+            .map_or(file_content.len(), |(offset, _)| offset)
+    };
+    Some(byte_offset(span.start)..byte_offset(span.end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_data() -> String {
+        "\u{feff}{
+  \"Materials\": [
+    {\"Color\": \"424242\", \"Emission\": 0},
+    {\"Color\": \"ffa500\", \"Emission\": 0,},
+  ],
+  \"Variants\": {
+    \"Fo\\\"o\": {
+      \"Materials\": {
+        \"1\": {\"Color\": \"ff0000\"}
+      }
+    }
+  }
+}"
+        .to_string()
+    }
+
+    fn path(segments: &[&str]) -> JsonPath {
+        segments.iter().map(|segment| segment.to_string()).collect()
+    }
+
+    #[test]
+    fn test_locate_json_path() {
+        let content = get_test_data();
+
+        assert_eq!(locate_json_path(&content, &Vec::new()), (1, 2));
+        assert_eq!(locate_json_path(&content, &path(&["Materials"])), (2, 3));
+        assert_eq!(
+            locate_json_path(&content, &path(&["Materials", "1"])),
+            (4, 5)
+        );
+        assert_eq!(
+            locate_json_path(&content, &path(&["Materials", "1", "Emission"])),
+            (4, 25)
+        );
+        assert_eq!(
+            locate_json_path(
+                &content,
+                &path(&["Variants", "Fo\"o", "Materials", "1", "Color"])
+            ),
+            (9, 15)
+        );
+    }
+
+    #[test]
+    fn test_locate_json_path_falls_back_to_parent() {
+        let content = get_test_data();
+
+        assert_eq!(
+            locate_json_path(&content, &path(&["Materials", "7", "Color"])),
+            (2, 3)
+        );
+    }
+
+    #[test]
+    fn test_locate_json_value() {
+        let content = get_test_data();
+
+        let color = locate_json_value(
+            &content,
+            &path(&["Variants", "Fo\"o", "Materials", "1", "Color"]),
+        )
+        .expect("Should locate color");
+        assert_eq!(&content[color], "\"ff0000\"");
+
+        let emission = locate_json_value(&content, &path(&["Materials", "1", "Emission"]))
+            .expect("Should locate emission");
+        assert_eq!(&content[emission], "0");
+
+        let materials = locate_json_value(&content, &path(&["Variants", "Fo\"o", "Materials"]))
+            .expect("Should locate materials");
+        let materials = &content[materials];
+        assert!(materials.starts_with('{') && materials.ends_with('}'));
+
+        assert!(locate_json_value(&content, &path(&["Materials", "7"])).is_none());
+    }
+}
@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::path::Path;
 
-use serde::Deserialize;
+use indexmap::IndexMap;
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use serde_path_to_error::Segment;
 
-#[derive(Deserialize, Debug, Clone)]
+use super::diagnostic::{format_json_path, render_source_snippet, JsonPath};
+
+/// Single palette entry. Values other than color are in 0-255 range.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 pub struct MaterialSchema {
+    /// Hex RGB color without leading #, e.g. "424242"
+    #[schemars(pattern(r"^[0-9a-fA-F]{6}$"))]
     pub color: String,
     pub company_tint: u8,
     pub emission: u8,
@@ -13,32 +22,162 @@ pub struct MaterialSchema {
     pub specular: u8,
 }
 
-pub type Variants = HashMap<String, VariantSchema>;
+pub type Variants = IndexMap<String, VariantSchema>;
 
-#[derive(Deserialize, Debug, Clone)]
+/// Variant overriding selected palette materials, may contain nested variants.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct VariantSchema {
-    pub materials: HashMap<u8, MaterialSchema>,
+    /// Overridden materials keyed by palette index
+    #[schemars(extend("propertyNames" = { "pattern": MATERIAL_INDEX_PATTERN }))]
+    pub materials: IndexMap<u8, MaterialSchema>,
+    /// Nested variants applied on top of this one
     pub variants: Option<Variants>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// Material definition of a Voxel Tycoon model (*.obj.meta).
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct VTMetaSchema {
+    /// Palette of 16 (4x4) or 64 (8x8) materials
+    #[schemars(extend("oneOf" = [
+        { "minItems": 16, "maxItems": 16 },
+        { "minItems": 64, "maxItems": 64 },
+    ]))]
     pub materials: Vec<MaterialSchema>,
+    /// Named variants overriding palette materials
     pub variants: Option<Variants>,
 }
 
+/// Palette index between 0 and 63 written as a JSON object key.
+pub const MATERIAL_INDEX_PATTERN: &str = r"^([0-9]|[1-5][0-9]|6[0-3])$";
+
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub path: JsonPath,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
 #[derive(Debug)]
 pub enum VTMetaReadError {
-    ParseError,
+    ParseError(ParseDiagnostic),
 }
 
-pub fn parse_material_json(file_content: &str) -> Result<VTMetaSchema, VTMetaReadError> {
-    match serde_json::from_str::<VTMetaSchema>(file_content) {
-        Ok(meta) => Ok(meta),
-        Err(_) => Err(VTMetaReadError::ParseError),
+impl VTMetaReadError {
+    /// Renders the error with the offending line of `file_content` and a caret
+    /// under the reported column.
+    pub fn render(&self, file_path: &Path, file_content: &str) -> String {
+        let VTMetaReadError::ParseError(diagnostic) = self;
+        let mut rendered = format!("error: {}\n", diagnostic.message);
+        if diagnostic.line > 0 {
+            rendered.push_str(&format!(
+                "  --> {}:{}:{}\n",
+                file_path.to_string_lossy(),
+                diagnostic.line,
+                diagnostic.column
+            ));
+            rendered.push_str(&render_source_snippet(
+                file_content,
+                diagnostic.line,
+                diagnostic.column,
+            ));
+        } else {
+            rendered.push_str(&format!("  --> {}\n", file_path.to_string_lossy()));
+        }
+        if !diagnostic.path.is_empty() {
+            rendered.push_str(&format!(
+                "   = path: {}\n",
+                format_json_path(&diagnostic.path)
+            ));
+        }
+        if let Some(expected) = &diagnostic.expected {
+            rendered.push_str(&format!("   = expected: {}\n", expected));
+        }
+        if let Some(found) = &diagnostic.found {
+            rendered.push_str(&format!("   = found: {}\n", found));
+        }
+        rendered
+    }
+}
+
+/// Splits serde messages like `invalid type: string "13", expected u8` into
+/// the found and expected parts.
+fn split_expectation(message: &str) -> (Option<String>, Option<String>) {
+    for prefix in ["invalid type: ", "invalid value: ", "invalid length "] {
+        if let Some(rest) = message.strip_prefix(prefix) {
+            if let Some((found, expected)) = rest.rsplit_once(", expected ") {
+                return (Some(expected.to_string()), Some(found.to_string()));
+            }
+        }
+    }
+    if let Some(field) = message.strip_prefix("missing field ") {
+        return (
+            Some(format!("field {}", field)),
+            Some("end of object".to_string()),
+        );
+    }
+    if let Some(field) = message.strip_prefix("unknown field ") {
+        if let Some((field, expected)) = field.split_once(", expected ") {
+            return (Some(expected.to_string()), Some(format!("field {}", field)));
+        }
     }
+    (None, None)
+}
+
+fn read_error_from(error: serde_path_to_error::Error<serde_json::Error>) -> VTMetaReadError {
+    let path: JsonPath = error
+        .path()
+        .iter()
+        .map(|segment| match segment {
+            Segment::Seq { index } => index.to_string(),
+            Segment::Map { key } | Segment::Enum { variant: key } => key.to_owned(),
+            Segment::Unknown => "?".to_string(),
+        })
+        .collect();
+    let inner = error.inner();
+    let full_message = inner.to_string();
+    let suffix = format!(" at line {} column {}", inner.line(), inner.column());
+    let message = full_message
+        .strip_suffix(&suffix)
+        .unwrap_or(&full_message)
+        .to_string();
+    let (expected, found) = split_expectation(&message);
+
+    VTMetaReadError::ParseError(ParseDiagnostic {
+        message,
+        line: inner.line(),
+        column: inner.column(),
+        path,
+        expected,
+        found,
+    })
+}
+
+fn parse_json<T: DeserializeOwned>(file_content: &str) -> Result<T, VTMetaReadError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(file_content);
+    serde_path_to_error::deserialize::<_, T>(deserializer).map_err(read_error_from)
+}
+
+/// Deserializes an already parsed document. Errors carry the JSON path but no
+/// line and column, those are reported as 0.
+pub fn deserialize_json_value<T: DeserializeOwned>(value: Value) -> Result<T, VTMetaReadError> {
+    serde_path_to_error::deserialize::<_, T>(value).map_err(read_error_from)
+}
+
+pub fn parse_json_value(file_content: &str) -> Result<Value, VTMetaReadError> {
+    parse_json::<Value>(file_content)
+}
+
+pub fn parse_material_json(file_content: &str) -> Result<VTMetaSchema, VTMetaReadError> {
+    parse_json::<VTMetaSchema>(file_content)
+}
+
+pub fn parse_variants_json(file_content: &str) -> Result<Variants, VTMetaReadError> {
+    parse_json::<Variants>(file_content)
 }
 
 #[cfg(test)]
@@ -59,7 +198,7 @@ mod tests {
 
         assert!(matches!(
             parse_material_json(data),
-            Err(VTMetaReadError::ParseError)
+            Err(VTMetaReadError::ParseError(_))
         ));
     }
 
@@ -80,7 +219,7 @@ mod tests {
 
         assert!(matches!(
             parse_material_json(data),
-            Err(VTMetaReadError::ParseError)
+            Err(VTMetaReadError::ParseError(_))
         ));
     }
 
@@ -102,10 +241,69 @@ mod tests {
 
         assert!(matches!(
             parse_material_json(data),
-            Err(VTMetaReadError::ParseError)
+            Err(VTMetaReadError::ParseError(_))
         ));
     }
 
+    #[test]
+    fn test_parse_material_json_error_details() {
+        let data = r#"{
+  "Materials": [],
+  "Variants": {
+    "foo": {
+      "Materials": {
+        "12": { "Color": "424242", "CompanyTint": 0, "Emission": 0, "Glassiness": 0, "Smoothness": 0, "Specular": "13" }
+      }
+    }
+  }
+}"#;
+
+        let Err(VTMetaReadError::ParseError(diagnostic)) = parse_material_json(data) else {
+            panic!("Should fail to parse");
+        };
+
+        assert_eq!(diagnostic.line, 6);
+        assert_eq!(diagnostic.column, 118);
+        assert_eq!(
+            format_json_path(&diagnostic.path),
+            "Variants.foo.Materials.12.Specular"
+        );
+        assert_eq!(diagnostic.expected, Some("u8".to_string()));
+        assert_eq!(diagnostic.found, Some("string \"13\"".to_string()));
+    }
+
+    #[test]
+    fn test_parse_material_json_error_missing_field_details() {
+        let data = r#"{ "Materials": [ { "Color": "424242" } ] }"#;
+
+        let Err(VTMetaReadError::ParseError(diagnostic)) = parse_material_json(data) else {
+            panic!("Should fail to parse");
+        };
+
+        assert_eq!(format_json_path(&diagnostic.path), "Materials.0");
+        assert_eq!(diagnostic.expected, Some("field `CompanyTint`".to_string()));
+    }
+
+    #[test]
+    fn test_render_parse_error() {
+        let data = "{\n  \"Materials\": [\n    { \"Color\": 42 }\n  ]\n}";
+        let error = parse_material_json(data).expect_err("Should fail to parse");
+        let rendered = error.render(Path::new("model.obj.meta"), data);
+
+        assert_eq!(
+            rendered,
+            r#"error: invalid type: integer `42`, expected a string
+  --> model.obj.meta:3:17
+   |
+ 3 |     { "Color": 42 }
+   |                 ^
+   = path: Materials.0.Color
+   = expected: a string
+   = found: integer `42`
+"#
+        );
+    }
+
     #[test]
     fn test_parse_material_json_success() {
         let data = r#"
@@ -137,7 +335,7 @@ mod tests {
         assert!(matches!(
             material_data
                 .materials
-                .get(0)
+                .first()
                 .expect("Should get first material"),
             _expected_material
         ));
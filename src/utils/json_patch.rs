@@ -0,0 +1,242 @@
+use std::ops::Range;
+
+use serde::Serialize;
+
+use super::{
+    diagnostic::JsonPath,
+    json_locate::locate_json_value,
+    json_parse::{MaterialSchema, VTMetaSchema},
+    variants::{get_variant_by_path, get_variant_paths_from_meta, variant_json_path},
+    write_meta::serialize_meta_json,
+};
+
+const MATERIAL_KEYS: [&str; 6] = [
+    "Color",
+    "CompanyTint",
+    "Emission",
+    "Glassiness",
+    "Smoothness",
+    "Specular",
+];
+
+enum Patch {
+    Replace(JsonPath, String),
+    /// New member of the object at the path
+    Insert(JsonPath, String, MaterialSchema),
+}
+
+fn material_values(material: &MaterialSchema) -> [String; 6] {
+    let number = |value: u8| value.to_string();
+    [
+        serde_json::to_string(&material.color).unwrap_or_default(),
+        number(material.company_tint),
+        number(material.emission),
+        number(material.glassiness),
+        number(material.smoothness),
+        number(material.specular),
+    ]
+}
+
+fn diff_material(
+    path: JsonPath,
+    original: &MaterialSchema,
+    edited: &MaterialSchema,
+    patches: &mut Vec<Patch>,
+) {
+    let original_values = material_values(original);
+    for (position, value) in material_values(edited).into_iter().enumerate() {
+        if value != original_values[position] {
+            let mut value_path = path.clone();
+            value_path.push(MATERIAL_KEYS[position].to_string());
+            patches.push(Patch::Replace(value_path, value));
+        }
+    }
+}
+
+/// Changes needed to turn `original` into `edited`. `None` when variants were
+/// added, removed or lost an override, which can't be patched in place.
+fn diff_meta(original: &VTMetaSchema, edited: &VTMetaSchema) -> Option<Vec<Patch>> {
+    if original.materials.len() != edited.materials.len() {
+        return None;
+    }
+    let mut patches: Vec<Patch> = Vec::new();
+    for (index, (original_material, edited_material)) in original
+        .materials
+        .iter()
+        .zip(edited.materials.iter())
+        .enumerate()
+    {
+        let path = vec!["Materials".to_string(), index.to_string()];
+        diff_material(path, original_material, edited_material, &mut patches);
+    }
+
+    let variant_paths = get_variant_paths_from_meta(edited);
+    if variant_paths != get_variant_paths_from_meta(original) {
+        return None;
+    }
+    for variant_path in variant_paths.iter() {
+        let original_variant = get_variant_by_path(original, variant_path)?;
+        let edited_variant = get_variant_by_path(edited, variant_path)?;
+        if original_variant
+            .materials
+            .keys()
+            .any(|index| !edited_variant.materials.contains_key(index))
+        {
+            return None;
+        }
+        let mut materials_path = variant_json_path(variant_path);
+        materials_path.push("Materials".to_string());
+        for (index, edited_material) in edited_variant.materials.iter() {
+            match original_variant.materials.get(index) {
+                Some(original_material) => {
+                    let mut path = materials_path.clone();
+                    path.push(index.to_string());
+                    diff_material(path, original_material, edited_material, &mut patches);
+                }
+                None => patches.push(Patch::Insert(
+                    materials_path.clone(),
+                    index.to_string(),
+                    edited_material.clone(),
+                )),
+            }
+        }
+    }
+    Some(patches)
+}
+
+fn serialize_member<T: Serialize>(key: &str, value: &T, indent: &str) -> Option<String> {
+    let value = serialize_meta_json(value).ok()?;
+    let value = value.replace('\n', &format!("\n{}", indent));
+    Some(format!("{}: {}", serde_json::to_string(key).ok()?, value))
+}
+
+/// Adds a member at the end of the object in `span`, using the whitespace in
+/// front of the first member as separator so the layout of the file is kept.
+fn insert_member(
+    content: &str,
+    span: Range<usize>,
+    key: &str,
+    material: &MaterialSchema,
+) -> Option<String> {
+    let object = &content[span.clone()];
+    let inner = object.strip_prefix('{')?.strip_suffix('}')?;
+    let trimmed = inner.trim_end();
+    let insert_at = span.start + 1 + trimmed.len();
+
+    let member = if trimmed.trim_start().is_empty() {
+        serialize_member(key, material, "")?
+    } else {
+        let separator = &inner[..inner.len() - inner.trim_start().len()];
+        let indent = separator.rsplit('\n').next().unwrap_or("");
+        let member = serialize_member(key, material, indent)?;
+        let comma = if trimmed.ends_with(',') { "" } else { "," };
+        format!("{}{}{}", comma, separator, member)
+    };
+
+    let mut patched = content.to_string();
+    patched.insert_str(insert_at, &member);
+    Some(patched)
+}
+
+/// Applies the differences between `original` and `edited` to the source text
+/// of `original`, leaving formatting, key order and untouched values as they
+/// were. Returns `None` when the edit can't be expressed as an in-place patch.
+pub fn patch_meta_content(
+    content: &str,
+    original: &VTMetaSchema,
+    edited: &VTMetaSchema,
+) -> Option<String> {
+    let mut patched = content.to_string();
+    for patch in diff_meta(original, edited)? {
+        patched = match patch {
+            Patch::Replace(path, value) => {
+                let span = locate_json_value(&patched, &path)?;
+                let mut replaced = patched.clone();
+                replaced.replace_range(span, &value);
+                replaced
+            }
+            Patch::Insert(path, key, material) => {
+                let span = locate_json_value(&patched, &path)?;
+                insert_member(&patched, span, &key, &material)?
+            }
+        };
+    }
+    Some(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::parse_material_json;
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        let base: Vec<String> = (0..16)
+            .map(|_| {
+                r#"{"Color": "424242", "CompanyTint": 0, "Emission": 0, "Glassiness": 0, "Smoothness": 0, "Specular": 0}"#
+                    .to_string()
+            })
+            .collect();
+        format!(
+            "{{\n  \"Materials\": [\n    {}\n  ],\n  \"Variants\": {{\n    \"night\": {{\n      \"Materials\": {{\n        \"1\": {{\"Color\": \"FF0000\", \"CompanyTint\": 0, \"Emission\": 9, \"Glassiness\": 0, \"Smoothness\": 0, \"Specular\": 0}}\n      }},\n      \"Variants\": null\n    }}\n  }}\n}}",
+            base.join(",\n    ")
+        )
+    }
+
+    #[test]
+    fn test_patch_meta_content_replaces_values() {
+        let content = get_test_data();
+        let original = parse_material_json(&content).expect("Should create meta");
+        let mut edited = original.clone();
+        edited.materials[2].emission = 200;
+        edited.variants.as_mut().expect("Should have variants")["night"]
+            .materials
+            .get_mut(&1)
+            .expect("Should have override")
+            .color = "00ff00".to_string();
+
+        let patched = patch_meta_content(&content, &original, &edited).expect("Should patch");
+        assert_eq!(patched.len(), content.len() + 2);
+        assert!(patched.contains(r#""Emission": 200,"#));
+        assert!(patched.contains(r#""1": {"Color": "00ff00", "CompanyTint": 0, "Emission": 9,"#));
+        assert_eq!(patched.matches(r#""Emission": 200"#).count(), 1);
+
+        let reparsed = parse_material_json(&patched).expect("Should parse patched file");
+        assert_eq!(reparsed.materials[2].emission, 200);
+    }
+
+    #[test]
+    fn test_patch_meta_content_inserts_override() {
+        let content = get_test_data();
+        let original = parse_material_json(&content).expect("Should create meta");
+        let mut edited = original.clone();
+        let mut material = edited.materials[5].clone();
+        material.specular = 42;
+        edited.variants.as_mut().expect("Should have variants")["night"]
+            .materials
+            .insert(5, material);
+
+        let patched = patch_meta_content(&content, &original, &edited).expect("Should patch");
+        assert!(patched.contains("\"Specular\": 0},\n        \"5\": {\n          \"Color\""));
+        assert!(patched.contains("          \"Specular\": 42\n        }\n      },"));
+
+        let reparsed = parse_material_json(&patched).expect("Should parse patched file");
+        let night = &reparsed.variants.expect("Should have variants")["night"];
+        assert_eq!(night.materials[&5].specular, 42);
+    }
+
+    #[test]
+    fn test_patch_meta_content_unchanged_and_unsupported() {
+        let content = get_test_data();
+        let original = parse_material_json(&content).expect("Should create meta");
+
+        assert_eq!(
+            patch_meta_content(&content, &original, &original).as_deref(),
+            Some(content.as_str())
+        );
+
+        let mut edited = original.clone();
+        edited.variants = None;
+        assert!(patch_meta_content(&content, &original, &edited).is_none());
+    }
+}
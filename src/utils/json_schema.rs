@@ -0,0 +1,52 @@
+use schemars::{generate::SchemaSettings, Schema};
+
+use super::json_parse::VTMetaSchema;
+
+/// JSON Schema (draft-07, supported by most editors) generated from the same
+/// types used to parse .meta files.
+pub fn get_meta_json_schema() -> Schema {
+    let generator = SchemaSettings::draft07().into_generator();
+    generator.into_root_schema_for::<VTMetaSchema>()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn test_get_meta_json_schema() {
+        let schema = get_meta_json_schema();
+        let schema: &Value = schema.as_value();
+
+        assert_eq!(schema["required"], serde_json::json!(["Materials"]));
+        let palette_sizes: Vec<(&Value, &Value)> = schema["properties"]["Materials"]["oneOf"]
+            .as_array()
+            .expect("Should list palette sizes")
+            .iter()
+            .map(|size| (&size["minItems"], &size["maxItems"]))
+            .collect();
+        assert_eq!(
+            palette_sizes,
+            vec![
+                (&Value::from(16), &Value::from(16)),
+                (&Value::from(64), &Value::from(64))
+            ]
+        );
+
+        let material = &schema["definitions"]["MaterialSchema"];
+        assert_eq!(
+            material["properties"]["Color"]["pattern"],
+            "^[0-9a-fA-F]{6}$"
+        );
+        assert_eq!(material["properties"]["Specular"]["maximum"], 255);
+
+        let variant = &schema["definitions"]["VariantSchema"];
+        assert_eq!(
+            variant["properties"]["Variants"]["additionalProperties"]["$ref"],
+            "#/definitions/VariantSchema"
+        );
+        assert!(variant["properties"]["Materials"]["propertyNames"]["pattern"].is_string());
+    }
+}
@@ -0,0 +1,322 @@
+use serde_json::{Map, Value};
+
+use super::{
+    diagnostic::{Diagnostic, JsonPath, Severity},
+    json_parse::{
+        deserialize_json_value, parse_json_value, parse_material_json, parse_variants_json,
+        VTMetaReadError, VTMetaSchema, Variants,
+    },
+};
+
+pub const LENIENT_REPAIR: &str = "lenient-repair";
+
+const NUMERIC_FIELDS: [&str; 5] = [
+    "CompanyTint",
+    "Emission",
+    "Glassiness",
+    "Smoothness",
+    "Specular",
+];
+
+fn repair(path: &JsonPath, message: String, repairs: &mut Vec<Diagnostic>) {
+    repairs.push(Diagnostic {
+        rule: LENIENT_REPAIR,
+        severity: Severity::Warning,
+        path: path.clone(),
+        message,
+    });
+}
+
+fn child_path(path: &JsonPath, segment: &str) -> JsonPath {
+    let mut child = path.clone();
+    child.push(segment.to_string());
+    child
+}
+
+/// Removes the byte order mark and trailing commas. Removed commas are
+/// replaced with spaces so line and column of every other token stay the same.
+fn repair_text(file_content: &str, repairs: &mut Vec<Diagnostic>) -> String {
+    let content = match file_content.strip_prefix('\u{feff}') {
+        Some(content) => {
+            repair(
+                &Vec::new(),
+                "Removed UTF-8 byte order mark".to_string(),
+                repairs,
+            );
+            content
+        }
+        None => file_content,
+    };
+
+    let chars: Vec<char> = content.chars().collect();
+    let mut repaired = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut line = 1;
+    let mut column = 0;
+
+    for (position, c) in chars.iter().enumerate() {
+        column += 1;
+        if *c == '\n' {
+            line += 1;
+            column = 0;
+        }
+
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if *c == '\\' {
+                escaped = true;
+            } else if *c == '"' {
+                in_string = false;
+            }
+        } else if *c == '"' {
+            in_string = true;
+        } else if *c == ',' {
+            let next = chars[position + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}') | Some(']')) {
+                repair(
+                    &Vec::new(),
+                    format!("Removed trailing comma at line {} column {}", line, column),
+                    repairs,
+                );
+                repaired.push(' ');
+                continue;
+            }
+        }
+        repaired.push(*c);
+    }
+
+    repaired
+}
+
+fn repair_color(color: &str) -> Option<(String, &'static str)> {
+    let hex = color.trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match (hex.len(), color.starts_with('#')) {
+        (6, true) => Some((hex.to_string(), "Removed leading # from color")),
+        (8, true) => Some((
+            hex[..6].to_string(),
+            "Removed leading # and alpha channel from color",
+        )),
+        (8, false) => Some((hex[..6].to_string(), "Removed alpha channel from color")),
+        _ => None,
+    }
+}
+
+fn repair_number(value: &Value) -> Option<(u8, &'static str)> {
+    match value {
+        Value::String(text) => text
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.fract() == 0.0 && (0.0..=255.0).contains(number))
+            .map(|number| (number as u8, "Converted number stored as string")),
+        Value::Number(number) if !number.is_u64() => number
+            .as_f64()
+            .filter(|number| number.fract() == 0.0 && (0.0..=255.0).contains(number))
+            .map(|number| (number as u8, "Converted fractional number to integer")),
+        _ => None,
+    }
+}
+
+fn repair_material(material: &mut Value, path: &JsonPath, repairs: &mut Vec<Diagnostic>) {
+    let Value::Object(fields) = material else {
+        return;
+    };
+
+    if let Some(Value::String(color)) = fields.get_mut("Color") {
+        if let Some((repaired, message)) = repair_color(color) {
+            repair(
+                &child_path(path, "Color"),
+                format!("{}: \"{}\" -> \"{}\"", message, color, repaired),
+                repairs,
+            );
+            *color = repaired;
+        }
+    }
+
+    for field in NUMERIC_FIELDS {
+        if let Some(value) = fields.get_mut(field) {
+            if let Some((repaired, message)) = repair_number(value) {
+                repair(
+                    &child_path(path, field),
+                    format!("{}: {} -> {}", message, value, repaired),
+                    repairs,
+                );
+                *value = Value::from(repaired);
+            }
+        }
+    }
+}
+
+fn repair_variants(variants: &mut Value, path: &JsonPath, repairs: &mut Vec<Diagnostic>) {
+    let Value::Object(variants) = variants else {
+        return;
+    };
+
+    for (name, variant) in variants.iter_mut() {
+        let variant_path = child_path(path, name);
+        let Value::Object(variant) = variant else {
+            continue;
+        };
+        if let Some(Value::Object(materials)) = variant.get_mut("Materials") {
+            let materials_path = child_path(&variant_path, "Materials");
+            for (index, material) in materials.iter_mut() {
+                repair_material(material, &child_path(&materials_path, index), repairs);
+            }
+        }
+        if let Some(sub_variants) = variant.get_mut("Variants") {
+            repair_variants(
+                sub_variants,
+                &child_path(&variant_path, "Variants"),
+                repairs,
+            );
+        }
+    }
+}
+
+fn repair_meta(meta: &mut Map<String, Value>, repairs: &mut Vec<Diagnostic>) {
+    if let Some(Value::Array(materials)) = meta.get_mut("Materials") {
+        for (index, material) in materials.iter_mut().enumerate() {
+            let path = vec!["Materials".to_string(), index.to_string()];
+            repair_material(material, &path, repairs);
+        }
+    }
+    if let Some(variants) = meta.get_mut("Variants") {
+        repair_variants(variants, &vec!["Variants".to_string()], repairs);
+    }
+}
+
+/// Accepts files from older tool versions and hand edits: byte order mark,
+/// trailing commas, `#` prefixed or RGBA colors and numbers stored as strings.
+/// Every applied fix is returned as a warning.
+pub fn parse_material_json_lenient(
+    file_content: &str,
+) -> Result<(VTMetaSchema, Vec<Diagnostic>), VTMetaReadError> {
+    let mut repairs: Vec<Diagnostic> = Vec::new();
+    let content = repair_text(file_content, &mut repairs);
+    let mut value = parse_json_value(&content)?;
+    if let Value::Object(meta) = &mut value {
+        repair_meta(meta, &mut repairs);
+    }
+    let meta = deserialize_json_value::<VTMetaSchema>(value)?;
+    Ok((meta, repairs))
+}
+
+pub fn parse_variants_json_lenient(
+    file_content: &str,
+) -> Result<(Variants, Vec<Diagnostic>), VTMetaReadError> {
+    let mut repairs: Vec<Diagnostic> = Vec::new();
+    let content = repair_text(file_content, &mut repairs);
+    let mut value = parse_json_value(&content)?;
+    repair_variants(&mut value, &Vec::new(), &mut repairs);
+    let variants = deserialize_json_value::<Variants>(value)?;
+    Ok((variants, repairs))
+}
+
+pub fn parse_meta(
+    file_content: &str,
+    lenient: bool,
+) -> Result<(VTMetaSchema, Vec<Diagnostic>), VTMetaReadError> {
+    if lenient {
+        return parse_material_json_lenient(file_content);
+    }
+    parse_material_json(file_content).map(|meta| (meta, Vec::new()))
+}
+
+pub fn parse_variants(
+    file_content: &str,
+    lenient: bool,
+) -> Result<(Variants, Vec<Diagnostic>), VTMetaReadError> {
+    if lenient {
+        return parse_variants_json_lenient(file_content);
+    }
+    parse_variants_json(file_content).map(|variants| (variants, Vec::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::diagnostic::format_json_path;
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        "\u{feff}{
+          \"Materials\": [
+            {\"Color\": \"#424242\",\"CompanyTint\": \"21\",\"Emission\": 37.0,\"Glassiness\": 69,\"Smoothness\": 66,\"Specular\": 13,},
+            {\"Color\": \"ffa500ff\",\"CompanyTint\": 0,\"Emission\": 0,\"Glassiness\": 0,\"Smoothness\": 0,\"Specular\": 0}
+          ],
+          \"Variants\": {
+            \"Foo\": {
+              \"Materials\": {
+                \"1\": {\"Color\": \"#FF0000\",\"CompanyTint\": 0,\"Emission\": 0,\"Glassiness\": 0,\"Smoothness\": \" 7 \",\"Specular\": 0}
+              },
+              \"Variants\": null
+            },
+          }
+        }"
+        .to_string()
+    }
+
+    #[test]
+    fn test_parse_material_json_lenient() {
+        let (meta, repairs) =
+            parse_material_json_lenient(&get_test_data()).expect("Should create meta");
+
+        assert_eq!(meta.materials[0].color, "424242");
+        assert_eq!(meta.materials[0].company_tint, 21);
+        assert_eq!(meta.materials[0].emission, 37);
+        assert_eq!(meta.materials[1].color, "ffa500");
+
+        let variant = &meta.variants.expect("Should have variants")["Foo"];
+        assert_eq!(variant.materials[&1].color, "FF0000");
+        assert_eq!(variant.materials[&1].smoothness, 7);
+
+        let paths: Vec<String> = repairs
+            .iter()
+            .map(|repair| format_json_path(&repair.path))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "<root>",
+                "<root>",
+                "<root>",
+                "Materials.0.Color",
+                "Materials.0.CompanyTint",
+                "Materials.0.Emission",
+                "Materials.1.Color",
+                "Variants.Foo.Materials.1.Color",
+                "Variants.Foo.Materials.1.Smoothness",
+            ]
+        );
+        assert!(repairs[1].message.ends_with("line 3 column 118"));
+    }
+
+    #[test]
+    fn test_parse_meta_strict_rejects_repairable_input() {
+        assert!(parse_meta(&get_test_data(), false).is_err());
+    }
+
+    #[test]
+    fn test_parse_material_json_lenient_keeps_real_errors() {
+        let data = r#"{ "Materials": [ {"Color": "42","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": "300"} ] }"#;
+
+        let Err(VTMetaReadError::ParseError(diagnostic)) = parse_material_json_lenient(data) else {
+            panic!("Should fail to parse");
+        };
+        assert_eq!(format_json_path(&diagnostic.path), "Materials.0.Specular");
+    }
+
+    #[test]
+    fn test_repair_text_ignores_commas_in_strings() {
+        let mut repairs = Vec::new();
+        let repaired = repair_text(r#"{"a": ", ]", "b": [1, 2,]}"#, &mut repairs);
+
+        assert_eq!(repaired, r#"{"a": ", ]", "b": [1, 2 ]}"#);
+        assert_eq!(repairs.len(), 1);
+    }
+}
@@ -0,0 +1,335 @@
+use super::{
+    color::{delta_e, rgb_to_lab, Lab},
+    diagnostic::{Diagnostic, JsonPath, Severity},
+    hex_to_rgb::hex_to_rgb,
+    json_parse::{MaterialSchema, VTMetaSchema},
+    palette::{get_palette_from_variant_path, VTPalette},
+    variants::{get_variant_by_path, get_variant_paths_from_meta, variant_json_path, VariantPath},
+};
+
+pub const SIMILAR_COLORS: &str = "similar-colors";
+pub const DARK_EMISSION: &str = "dark-emission";
+pub const GLASSINESS_WITHOUT_SPECULAR: &str = "glassiness-without-specular";
+pub const EMISSIVE_COMPANY_TINT: &str = "emissive-company-tint";
+pub const REDUNDANT_OVERRIDE: &str = "redundant-override";
+
+pub struct LintRule {
+    pub id: &'static str,
+    pub description: &'static str,
+}
+
+pub const LINT_RULES: [LintRule; 5] = [
+    LintRule {
+        id: SIMILAR_COLORS,
+        description: "Colors of different materials are almost impossible to tell apart",
+    },
+    LintRule {
+        id: DARK_EMISSION,
+        description: "Emissive material is too dark to be visible at night",
+    },
+    LintRule {
+        id: GLASSINESS_WITHOUT_SPECULAR,
+        description: "Glassiness has no visible effect without Specular",
+    },
+    LintRule {
+        id: EMISSIVE_COMPANY_TINT,
+        description: "Company tint is applied to emissive material",
+    },
+    LintRule {
+        id: REDUNDANT_OVERRIDE,
+        description: "Variant override is identical to the value it overrides",
+    },
+];
+
+pub struct LintSettings {
+    /// Colors closer than this CIEDE2000 distance are reported as similar
+    pub similar_colors_delta_e: f64,
+    /// Emissive materials with L* lightness below this value are reported as dark
+    pub dark_emission_lightness: f64,
+}
+
+impl Default for LintSettings {
+    fn default() -> Self {
+        LintSettings {
+            similar_colors_delta_e: 2.3,
+            dark_emission_lightness: 20.0,
+        }
+    }
+}
+
+fn warning(rule: &'static str, path: JsonPath, message: String) -> Diagnostic {
+    Diagnostic {
+        rule,
+        severity: Severity::Warning,
+        path,
+        message,
+    }
+}
+
+fn material_lab(material: &MaterialSchema) -> Option<Lab> {
+    hex_to_rgb(&material.color).ok().map(rgb_to_lab)
+}
+
+fn lint_material(
+    material: &MaterialSchema,
+    path: &JsonPath,
+    settings: &LintSettings,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if material.emission > 0 {
+        if let Some(lab) = material_lab(material) {
+            if lab.l < settings.dark_emission_lightness {
+                diagnostics.push(warning(
+                    DARK_EMISSION,
+                    path.clone(),
+                    format!(
+                        "Color {} is too dark for emission, lightness {:.1} is below {:.1}",
+                        material.color, lab.l, settings.dark_emission_lightness
+                    ),
+                ));
+            }
+        }
+        if material.company_tint > 0 {
+            diagnostics.push(warning(
+                EMISSIVE_COMPANY_TINT,
+                path.clone(),
+                format!(
+                    "CompanyTint {} is set on material with Emission {}",
+                    material.company_tint, material.emission
+                ),
+            ));
+        }
+    }
+
+    if material.glassiness > 0 && material.specular == 0 {
+        diagnostics.push(warning(
+            GLASSINESS_WITHOUT_SPECULAR,
+            path.clone(),
+            format!("Glassiness {} is set without Specular", material.glassiness),
+        ));
+    }
+}
+
+fn similar_colors_message(
+    material: &MaterialSchema,
+    other: &MaterialSchema,
+    other_index: usize,
+    settings: &LintSettings,
+) -> Option<String> {
+    let distance = delta_e(material_lab(material)?, material_lab(other)?);
+    if distance == 0.0 {
+        return Some(format!(
+            "Color {} is identical to {} of material {}",
+            material.color, other.color, other_index
+        ));
+    }
+    if distance < settings.similar_colors_delta_e {
+        return Some(format!(
+            "Color {} is nearly identical to {} of material {} (deltaE {:.2})",
+            material.color, other.color, other_index, distance
+        ));
+    }
+    None
+}
+
+fn lint_base_palette(
+    palette: &VTPalette,
+    settings: &LintSettings,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (index, material) in palette.iter().enumerate() {
+        let path = vec!["Materials".to_string(), index.to_string()];
+        lint_material(material, &path, settings, diagnostics);
+
+        for (other_index, other) in palette.iter().enumerate().take(index) {
+            if let Some(message) = similar_colors_message(material, other, other_index, settings) {
+                let mut color_path = path.clone();
+                color_path.push("Color".to_string());
+                diagnostics.push(warning(SIMILAR_COLORS, color_path, message));
+            }
+        }
+    }
+}
+
+fn lint_variant(
+    meta: &VTMetaSchema,
+    variant_path: &VariantPath,
+    settings: &LintSettings,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let Some(variant) = get_variant_by_path(meta, variant_path) else {
+        return;
+    };
+    let parent_path = variant_path[..variant_path.len() - 1].to_vec();
+    let Ok(parent_palette) = get_palette_from_variant_path(meta, &parent_path) else {
+        return;
+    };
+    let Ok(palette) = get_palette_from_variant_path(meta, variant_path) else {
+        return;
+    };
+
+    for (index, material) in variant.materials.iter() {
+        let index = *index as usize;
+        let mut path = variant_json_path(variant_path);
+        path.push("Materials".to_string());
+        path.push(index.to_string());
+        lint_material(material, &path, settings, diagnostics);
+
+        if parent_palette.get(index) == Some(material) {
+            diagnostics.push(warning(
+                REDUNDANT_OVERRIDE,
+                path.clone(),
+                format!(
+                    "Override of material {} is identical to the value it overrides",
+                    index
+                ),
+            ));
+        }
+
+        for (other_index, other) in palette.iter().enumerate() {
+            if other_index == index {
+                continue;
+            }
+            if let Some(message) = similar_colors_message(material, other, other_index, settings) {
+                let mut color_path = path.clone();
+                color_path.push("Color".to_string());
+                diagnostics.push(warning(SIMILAR_COLORS, color_path, message));
+            }
+        }
+    }
+}
+
+/// Checks for common art mistakes. All findings are warnings, severity can be
+/// changed per rule with `apply_rule_levels`.
+pub fn lint_meta(meta: &VTMetaSchema, settings: &LintSettings) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    lint_base_palette(&meta.materials, settings, &mut diagnostics);
+    for variant_path in get_variant_paths_from_meta(meta) {
+        lint_variant(meta, &variant_path, settings, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{diagnostic::format_json_path, json_parse::parse_material_json};
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        r#"
+        {
+          "Materials": [
+            {"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "434242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "0a0a0a","CompanyTint": 0,"Emission": 255,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffffff","CompanyTint": 0,"Emission": 0,"Glassiness": 200,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffa500","CompanyTint": 255,"Emission": 255,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "00ff00","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+          ],
+          "Variants": {
+            "Foo": {
+              "Materials": {
+                "5": {"Color": "00ff00","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+                "0": {"Color": "0000ff","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+              },
+              "Variants": null
+            }
+          }
+        }"#
+        .to_string()
+    }
+
+    #[test]
+    fn test_lint_meta() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let diagnostics = lint_meta(&meta, &LintSettings::default());
+
+        let found: Vec<(&str, String)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.rule, format_json_path(&diagnostic.path)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (SIMILAR_COLORS, "Materials.1.Color".to_string()),
+                (DARK_EMISSION, "Materials.2".to_string()),
+                (GLASSINESS_WITHOUT_SPECULAR, "Materials.3".to_string()),
+                (EMISSIVE_COMPANY_TINT, "Materials.4".to_string()),
+                (REDUNDANT_OVERRIDE, "Variants.Foo.Materials.5".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_meta_identical_colors() {
+        let meta = parse_material_json(
+            r#"
+            {
+              "Materials": [
+                {"Color": "336699","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+                {"Color": "ffffff","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+                {"Color": "336699","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 100,"Specular": 0}
+              ],
+              "Variants": {
+                "Foo": {
+                  "Materials": {
+                    "1": {"Color": "336699","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+                  },
+                  "Variants": null
+                }
+              }
+            }"#,
+        )
+        .expect("Should create meta");
+        let settings = LintSettings {
+            similar_colors_delta_e: 0.1,
+            ..LintSettings::default()
+        };
+        let diagnostics = lint_meta(&meta, &settings);
+
+        let found: Vec<(String, &str)> = diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.rule == SIMILAR_COLORS)
+            .map(|diagnostic| {
+                (
+                    format_json_path(&diagnostic.path),
+                    diagnostic.message.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "Materials.2.Color".to_string(),
+                    "Color 336699 is identical to 336699 of material 0"
+                ),
+                (
+                    "Variants.Foo.Materials.1.Color".to_string(),
+                    "Color 336699 is identical to 336699 of material 0"
+                ),
+                (
+                    "Variants.Foo.Materials.1.Color".to_string(),
+                    "Color 336699 is identical to 336699 of material 2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_meta_settings() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let settings = LintSettings {
+            similar_colors_delta_e: 0.1,
+            dark_emission_lightness: 1.0,
+        };
+        let diagnostics = lint_meta(&meta, &settings);
+
+        assert!(diagnostics.iter().all(
+            |diagnostic| diagnostic.rule != SIMILAR_COLORS && diagnostic.rule != DARK_EMISSION
+        ));
+    }
+}
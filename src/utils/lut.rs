@@ -0,0 +1,143 @@
+use image::{ImageBuffer, Rgb, RgbImage};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use super::{
+    json_parse::VTMetaSchema,
+    palette::{get_palette_from_variant_path, GetPaletteError, VTPalette},
+    save_image::Colors,
+    variants::{format_variant_path, get_variant_paths_from_meta, VariantPath},
+};
+
+/// Version of the sidecar layout, bumped on incompatible changes
+pub const LUT_SIDECAR_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct LutRow {
+    pub row: usize,
+    /// `null` for the base palette
+    pub variant_path: Option<VariantPath>,
+    pub name: String,
+}
+
+/// Describes lookup textures of one model: which row holds which variant and
+/// which file holds which material property.
+#[derive(Debug, Serialize)]
+pub struct LutSidecar {
+    pub version: u32,
+    pub width: usize,
+    pub height: usize,
+    pub textures: IndexMap<String, String>,
+    pub rows: Vec<LutRow>,
+}
+
+/// Resolved palettes of the base `Materials` and every variant in file order,
+/// one per lookup texture row.
+pub fn get_lut_palettes(
+    meta: &VTMetaSchema,
+) -> Result<Vec<(Option<VariantPath>, VTPalette)>, GetPaletteError> {
+    let mut palettes = vec![(None, meta.materials.clone())];
+    for variant_path in get_variant_paths_from_meta(meta) {
+        let palette = get_palette_from_variant_path(meta, &variant_path)?;
+        palettes.push((Some(variant_path), palette));
+    }
+    Ok(palettes)
+}
+
+pub fn get_lut_rows(palettes: &[(Option<VariantPath>, VTPalette)]) -> Vec<LutRow> {
+    palettes
+        .iter()
+        .enumerate()
+        .map(|(row, (variant_path, _))| LutRow {
+            row,
+            variant_path: variant_path.clone(),
+            name: match variant_path {
+                Some(path) => format_variant_path(path),
+                None => "Materials".to_string(),
+            },
+        })
+        .collect()
+}
+
+/// One pixel per material, each row one palette starting at the top of the
+/// image with material 0 in the left column.
+pub fn build_lut_image(rows: &[Colors]) -> RgbImage {
+    let width = rows.iter().map(|colors| colors.len()).max().unwrap_or(0);
+    let mut img: RgbImage = ImageBuffer::new(width as u32, rows.len() as u32);
+    for (row, colors) in rows.iter().enumerate() {
+        for (index, color) in colors.iter().enumerate() {
+            img.put_pixel(index as u32, row as u32, Rgb(*color));
+        }
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{
+        get_colors_from_meta::{get_colors_from_palette, MaterialProperty},
+        json_parse::parse_material_json,
+    };
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        let material = |color: &str| {
+            format!(
+                r#"{{"Color": "{}","CompanyTint": 0,"Emission": 7,"Glassiness": 0,"Smoothness": 0,"Specular": 0}}"#,
+                color
+            )
+        };
+        let base: Vec<String> = (0..16).map(|_| material("000000")).collect();
+        format!(
+            r#"{{
+              "Materials": [{}],
+              "Variants": {{
+                "foo": {{
+                  "Materials": {{ "1": {} }},
+                  "Variants": {{ "bar": {{ "Materials": {{ "15": {} }}, "Variants": null }} }}
+                }},
+                "night": {{ "Materials": {{}}, "Variants": null }}
+              }}
+            }}"#,
+            base.join(","),
+            material("ff0000"),
+            material("0000ff")
+        )
+    }
+
+    #[test]
+    fn test_lut_rows_and_image() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let palettes = get_lut_palettes(&meta).expect("Should resolve palettes");
+
+        let names: Vec<String> = get_lut_rows(&palettes)
+            .into_iter()
+            .map(|row| row.name)
+            .collect();
+        assert_eq!(names, vec!["Materials", "foo", "foo/bar", "night"]);
+
+        let rows: Vec<Colors> = palettes
+            .iter()
+            .map(|(_, palette)| get_colors_from_palette(palette, &MaterialProperty::Color))
+            .collect();
+        let img = build_lut_image(&rows);
+        assert_eq!(img.dimensions(), (16, 4));
+        assert_eq!(img.get_pixel(1, 0), &Rgb([0, 0, 0]));
+        assert_eq!(img.get_pixel(1, 1), &Rgb([255, 0, 0]));
+        assert_eq!(img.get_pixel(1, 2), &Rgb([255, 0, 0]));
+        assert_eq!(img.get_pixel(15, 2), &Rgb([0, 0, 255]));
+        assert_eq!(img.get_pixel(15, 3), &Rgb([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_get_lut_palettes_index_out_of_range() {
+        let mut meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        meta.materials.truncate(8);
+
+        assert!(matches!(
+            get_lut_palettes(&meta),
+            Err(GetPaletteError::MaterialIndexOutOfRange(15))
+        ));
+    }
+}
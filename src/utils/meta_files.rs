@@ -0,0 +1,146 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Files picked from a directory when no `--include` is given
+pub const DEFAULT_META_INCLUDE: &str = "**/*.obj.meta";
+
+#[derive(Debug)]
+pub enum FindMetaFilesError {
+    /// Glob and the reason it can't be parsed
+    InvalidGlob((String, String)),
+    DirectoryRead(PathBuf),
+}
+
+/// Include and exclude globs matched against paths relative to the searched
+/// directory, `/` separated.
+pub struct MetaFileFilter {
+    include: GlobSet,
+    exclude: GlobSet,
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, FindMetaFilesError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| {
+                FindMetaFilesError::InvalidGlob((pattern.to_owned(), e.kind().to_string()))
+            })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map_err(|e| FindMetaFilesError::InvalidGlob((patterns.join(" "), e.to_string())))
+}
+
+impl MetaFileFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, FindMetaFilesError> {
+        let include = match include.is_empty() {
+            true => build_glob_set(&[DEFAULT_META_INCLUDE.to_string()])?,
+            false => build_glob_set(include)?,
+        };
+        Ok(MetaFileFilter {
+            include,
+            exclude: build_glob_set(exclude)?,
+        })
+    }
+
+    pub fn matches(&self, relative_path: &Path) -> bool {
+        self.include.is_match(relative_path) && !self.exclude.is_match(relative_path)
+    }
+}
+
+fn collect_meta_files(
+    root: &Path,
+    directory: &Path,
+    filter: &MetaFileFilter,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), FindMetaFilesError> {
+    let read_error = || FindMetaFilesError::DirectoryRead(directory.to_path_buf());
+    for entry in fs::read_dir(directory).map_err(|_| read_error())? {
+        let entry = entry.map_err(|_| read_error())?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|_| read_error())?;
+        if file_type.is_dir() {
+            collect_meta_files(root, &path, filter, files)?;
+        } else if let Ok(relative_path) = path.strip_prefix(root) {
+            if filter.matches(relative_path) {
+                files.push(relative_path.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Files under `root` passing `filter`, relative to `root` and sorted so runs
+/// are repeatable. Symbolic links are not followed.
+pub fn find_meta_files(
+    root: &Path,
+    filter: &MetaFileFilter,
+) -> Result<Vec<PathBuf>, FindMetaFilesError> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    collect_meta_files(root, root, filter, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_meta_file_filter() {
+        let filter = MetaFileFilter::new(&[], &patterns(&["backup/**"])).expect("Should build");
+        assert!(filter.matches(Path::new("bus.obj.meta")));
+        assert!(filter.matches(Path::new("vehicles/bus/bus.obj.meta")));
+        assert!(!filter.matches(Path::new("vehicles/bus/bus.png.meta")));
+        assert!(!filter.matches(Path::new("backup/bus.obj.meta")));
+
+        let filter =
+            MetaFileFilter::new(&patterns(&["vehicles/*.meta"]), &[]).expect("Should build");
+        assert!(filter.matches(Path::new("vehicles/bus.png.meta")));
+        assert!(!filter.matches(Path::new("vehicles/bus/bus.obj.meta")));
+
+        assert!(matches!(
+            MetaFileFilter::new(&patterns(&["vehicles/[a"]), &[]),
+            Err(FindMetaFilesError::InvalidGlob(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_meta_files() {
+        let root = std::env::temp_dir().join("vt-utils-test-find-meta-files");
+        let _ = fs::remove_dir_all(&root);
+        for file in [
+            "b.obj.meta",
+            "a/z.obj.meta",
+            "a/b/c.obj.meta",
+            "a/texture.png",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().expect("Should have parent"))
+                .expect("Should create directory");
+            fs::write(&path, "{}").expect("Should write file");
+        }
+
+        let filter = MetaFileFilter::new(&[], &[]).expect("Should build");
+        let files = find_meta_files(&root, &filter).expect("Should find files");
+        let _ = fs::remove_dir_all(&root);
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("a/b/c.obj.meta"),
+                PathBuf::from("a/z.obj.meta"),
+                PathBuf::from("b.obj.meta"),
+            ]
+        );
+    }
+}
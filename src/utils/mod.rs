@@ -1,6 +1,31 @@
+pub mod color;
+pub mod config;
+pub mod dedupe;
+pub mod diagnostic;
+pub mod editor;
+pub mod export_manifest;
 pub mod get_colors_from_meta;
 pub mod hex_to_rgb;
+pub mod jobs;
+pub mod json_locate;
 pub mod json_parse;
+pub mod json_patch;
+pub mod json_schema;
+pub mod lenient_parse;
+pub mod lint;
+pub mod lut;
+pub mod meta_files;
+pub mod name_template;
+pub mod output;
 pub mod palette;
+pub mod provenance;
+pub mod report;
 pub mod save_image;
+pub mod terminal_image;
+pub mod validate;
+pub mod variant_select;
+pub mod variant_transfer;
+pub mod variant_tree;
 pub mod variants;
+pub mod watch;
+pub mod write_meta;
@@ -0,0 +1,233 @@
+use std::path::{Component, Path};
+
+use super::get_colors_from_meta::MaterialProperty;
+
+/// Placeholders of `--name-template`, in the order they are documented
+pub const NAME_PLACEHOLDERS: [&str; 8] = [
+    "stem",
+    "model",
+    "property",
+    "prop",
+    "variant",
+    "variant_path",
+    "layout",
+    "ext",
+];
+
+#[derive(Debug, PartialEq)]
+pub enum NameTemplateError {
+    UnknownPlaceholder(String),
+    UnclosedPlaceholder,
+    /// Absolute paths and `..` would write outside of the output directory
+    OutsideOutputDirectory,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Placeholder(String),
+}
+
+/// Output file name with `{placeholder}`s, `/` creates subdirectories.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameTemplate {
+    parts: Vec<Part>,
+}
+
+/// Values of one output file. Empty values are allowed, e.g. `variant` of the
+/// base palette.
+#[derive(Debug, Default)]
+pub struct NameValues<'a> {
+    pub stem: &'a str,
+    pub property: Option<MaterialProperty>,
+    pub variant: &'a str,
+    pub variant_path: &'a str,
+    pub layout: &'a str,
+    pub ext: &'a str,
+}
+
+/// Short property names used by `{prop}`.
+pub fn property_abbreviation(property: &MaterialProperty) -> &'static str {
+    match property {
+        MaterialProperty::Color => "col",
+        MaterialProperty::CompanyTint => "tint",
+        MaterialProperty::Emission => "emit",
+        MaterialProperty::Glassiness => "glass",
+        MaterialProperty::Smoothness => "smooth",
+        MaterialProperty::Specular => "spec",
+    }
+}
+
+/// Separators dropped before a placeholder with an empty value, so
+/// `{stem}-{variant}` of the base palette doesn't end with `-`.
+const SEPARATORS: [char; 3] = ['-', '_', '.'];
+
+impl NameTemplate {
+    pub fn parse(template: &str) -> Result<Self, NameTemplateError> {
+        let mut parts: Vec<Part> = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..]
+                .find('}')
+                .ok_or(NameTemplateError::UnclosedPlaceholder)?;
+            let name = &rest[start + 1..start + end];
+            if !NAME_PLACEHOLDERS.contains(&name) {
+                return Err(NameTemplateError::UnknownPlaceholder(name.to_string()));
+            }
+            parts.push(Part::Placeholder(name.to_string()));
+            rest = &rest[start + end + 1..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.to_string()));
+        }
+        if !parts.contains(&Part::Placeholder("ext".to_string())) {
+            parts.push(Part::Text(".".to_string()));
+            parts.push(Part::Placeholder("ext".to_string()));
+        }
+
+        let name_template = NameTemplate { parts };
+        let example = name_template.render_parts(&NameValues {
+            stem: "stem",
+            property: Some(MaterialProperty::Color),
+            variant: "variant",
+            variant_path: "variant",
+            layout: "grid",
+            ext: "png",
+        });
+        let stays_inside = Path::new(&example)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if !stays_inside {
+            return Err(NameTemplateError::OutsideOutputDirectory);
+        }
+        Ok(name_template)
+    }
+
+    fn render_parts(&self, values: &NameValues) -> String {
+        let model = values.stem.strip_suffix(".obj").unwrap_or(values.stem);
+        let mut name = String::new();
+        for part in self.parts.iter() {
+            let value = match part {
+                Part::Text(text) => {
+                    name.push_str(text);
+                    continue;
+                }
+                Part::Placeholder(placeholder) => match placeholder.as_str() {
+                    "stem" => values.stem.to_string(),
+                    "model" => model.to_string(),
+                    "property" => values
+                        .property
+                        .map(|property| property.to_string())
+                        .unwrap_or_default(),
+                    "prop" => values
+                        .property
+                        .map(|property| property_abbreviation(&property).to_string())
+                        .unwrap_or_default(),
+                    "variant" => values.variant.to_string(),
+                    "variant_path" => values.variant_path.to_string(),
+                    "layout" => values.layout.to_string(),
+                    _ => values.ext.to_string(),
+                },
+            };
+            if value.is_empty() {
+                if let Some(separator) = name.pop() {
+                    if !SEPARATORS.contains(&separator) {
+                        name.push(separator);
+                    }
+                }
+            }
+            name.push_str(&value);
+        }
+        name
+    }
+
+    /// File name relative to the output directory.
+    pub fn render(&self, values: &NameValues) -> String {
+        // Empty directories of empty values are skipped, values like a `..`
+        // variant name can't leave the output directory
+        self.render_parts(values)
+            .split('/')
+            .filter(|segment| !matches!(*segment, "" | "." | ".."))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, values: &NameValues) -> String {
+        NameTemplate::parse(template)
+            .expect("Should parse template")
+            .render(values)
+    }
+
+    #[test]
+    fn test_name_template_render() {
+        let values = NameValues {
+            stem: "bus.obj",
+            property: Some(MaterialProperty::CompanyTint),
+            variant: "rain",
+            variant_path: "night/rain",
+            layout: "grid",
+            ext: "png",
+        };
+        assert_eq!(
+            render("{stem}-{property}-{variant}", &values),
+            "bus.obj-company-tint-rain.png"
+        );
+        assert_eq!(
+            render(
+                "textures/{model}/{variant_path}/{model}_{prop}.{ext}",
+                &values
+            ),
+            "textures/bus/night/rain/bus_tint.png"
+        );
+
+        let base = NameValues {
+            variant: "",
+            variant_path: "",
+            ..values
+        };
+        assert_eq!(render("{model}_{prop}_{variant}", &base), "bus_tint.png");
+        assert_eq!(
+            render("{variant_path}/{model}_{prop}", &base),
+            "bus_tint.png"
+        );
+        assert_eq!(
+            render(
+                "{model}-{layout}",
+                &NameValues {
+                    ext: "json",
+                    property: None,
+                    ..base
+                }
+            ),
+            "bus-grid.json"
+        );
+    }
+
+    #[test]
+    fn test_name_template_errors() {
+        assert_eq!(
+            NameTemplate::parse("{model}-{colour}"),
+            Err(NameTemplateError::UnknownPlaceholder("colour".to_string()))
+        );
+        assert_eq!(
+            NameTemplate::parse("{model"),
+            Err(NameTemplateError::UnclosedPlaceholder)
+        );
+        assert_eq!(
+            NameTemplate::parse("../{model}"),
+            Err(NameTemplateError::OutsideOutputDirectory)
+        );
+        assert_eq!(
+            NameTemplate::parse("/tmp/{model}"),
+            Err(NameTemplateError::OutsideOutputDirectory)
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+use super::{
+    diagnostic::{format_diagnostics, Diagnostic, Severity},
+    report::{FileReport, Finding},
+};
+
+/// Version of the JSON envelope. Bumped only on incompatible changes, new
+/// fields can be added without a bump.
+pub const OUTPUT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WrittenFile {
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Text kept by a buffered output until it is merged.
+enum BufferedText {
+    Stdout(String),
+    Stderr(String),
+}
+
+/// Collects results of a command. In text mode messages are printed right
+/// away, in JSON mode everything is kept for a single envelope printed at the end.
+/// A buffered output keeps printed text as well, so work done in parallel can
+/// be merged in a fixed order.
+pub struct Output {
+    format: OutputFormat,
+    buffer: Option<Vec<BufferedText>>,
+    files: Vec<WrittenFile>,
+    warnings: Vec<Value>,
+    errors: Vec<Value>,
+    result: Map<String, Value>,
+}
+
+fn finding_json(file_path: &Path, finding: &Finding) -> Value {
+    let position = |value: usize| match value {
+        0 => Value::Null,
+        value => Value::from(value),
+    };
+    json!({
+        "file": file_path.to_string_lossy(),
+        "rule": finding.diagnostic.rule,
+        "path": finding.diagnostic.path,
+        "line": position(finding.line),
+        "column": position(finding.column),
+        "message": finding.diagnostic.message,
+    })
+}
+
+pub fn hash_file(path: &Path) -> Option<WrittenFile> {
+    let content = fs::read(path).ok()?;
+    Some(WrittenFile {
+        path: path.to_string_lossy().to_string(),
+        bytes: content.len() as u64,
+        sha256: format!("{:x}", Sha256::digest(&content)),
+    })
+}
+
+impl Output {
+    pub fn new(format: OutputFormat) -> Self {
+        Output {
+            format,
+            buffer: None,
+            files: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            result: Map::new(),
+        }
+    }
+
+    /// Output of the same format which prints nothing until merged.
+    pub fn buffered(&self) -> Self {
+        Output {
+            buffer: Some(Vec::new()),
+            ..Output::new(self.format)
+        }
+    }
+
+    /// Prints the text of a buffered output and adds its files, findings and
+    /// results, as if everything was written to this output.
+    pub fn merge(&mut self, other: Output) {
+        for text in other.buffer.unwrap_or_default() {
+            match text {
+                BufferedText::Stdout(text) => self.write_stdout(text),
+                BufferedText::Stderr(text) => self.write_stderr(text),
+            }
+        }
+        self.files.extend(other.files);
+        self.warnings.extend(other.warnings);
+        self.errors.extend(other.errors);
+        self.result.extend(other.result);
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    fn write_stdout(&mut self, text: String) {
+        match &mut self.buffer {
+            Some(buffer) => buffer.push(BufferedText::Stdout(text)),
+            None => print!("{}", text),
+        }
+    }
+
+    fn write_stderr(&mut self, text: String) {
+        match &mut self.buffer {
+            Some(buffer) => buffer.push(BufferedText::Stderr(text)),
+            None => eprint!("{}", text),
+        }
+    }
+
+    /// Prints a line in text mode.
+    pub fn message(&mut self, text: &str) {
+        if !self.is_json() {
+            self.write_stdout(format!("{}\n", text));
+        }
+    }
+
+    /// Prints a line to standard error in text mode.
+    pub fn error_message(&mut self, text: &str) {
+        if !self.is_json() {
+            self.write_stderr(format!("{}\n", text));
+        }
+    }
+
+    /// Prints already formatted text in text mode.
+    pub fn print(&mut self, text: &str) {
+        if !self.is_json() {
+            self.write_stdout(text.to_string());
+        }
+    }
+
+    /// Adds findings to the JSON warnings and errors, nothing is printed.
+    pub fn collect(&mut self, report: &FileReport) {
+        for finding in report.findings.iter() {
+            let finding_value = finding_json(&report.file_path, finding);
+            match finding.diagnostic.severity {
+                Severity::Warning => self.warnings.push(finding_value),
+                Severity::Error => self.errors.push(finding_value),
+            }
+        }
+    }
+
+    /// Findings of checking commands, printed to standard output in text mode.
+    pub fn report(&mut self, report: &FileReport) {
+        if self.is_json() {
+            self.collect(report);
+        } else {
+            self.write_stdout(format_diagnostics(
+                &report.file_path,
+                &diagnostics_of(report),
+            ));
+        }
+    }
+
+    /// Warnings about repaired input, printed to standard error in text mode
+    /// so they don't mix with data written to standard output.
+    pub fn report_repairs(&mut self, report: &FileReport) {
+        if self.is_json() {
+            self.collect(report);
+        } else {
+            self.write_stderr(format_diagnostics(
+                &report.file_path,
+                &diagnostics_of(report),
+            ));
+        }
+    }
+
+    /// Records a file written by the command with its size and hash.
+    pub fn file_written(&mut self, path: &Path) {
+        if self.is_json() {
+            if let Some(written_file) = hash_file(path) {
+                self.files.push(written_file);
+            }
+        }
+    }
+
+    pub fn set_result<T: Serialize>(&mut self, key: &str, value: T) {
+        if self.is_json() {
+            self.result.insert(
+                key.to_string(),
+                serde_json::to_value(value).unwrap_or(Value::Null),
+            );
+        }
+    }
+
+    /// Envelope with the outcome of `command`, `failure` holds exit code and
+    /// message when the command failed.
+    pub fn to_json(&self, command: &str, failure: Option<(u8, String)>) -> Value {
+        let (exit_code, failure) = match failure {
+            Some((exit_code, message)) => (
+                exit_code,
+                json!({ "exit_code": exit_code, "message": message }),
+            ),
+            None => (0, Value::Null),
+        };
+        json!({
+            "version": OUTPUT_VERSION,
+            "tool": { "name": "vt-utils", "version": env!("CARGO_PKG_VERSION") },
+            "command": command,
+            "success": exit_code == 0,
+            "exit_code": exit_code,
+            "failure": failure,
+            "files": self.files,
+            "warnings": self.warnings,
+            "errors": self.errors,
+            "result": self.result,
+        })
+    }
+
+    pub fn print_json(&self, command: &str, failure: Option<(u8, String)>) {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&self.to_json(command, failure)).unwrap_or_default()
+        );
+    }
+}
+
+fn diagnostics_of(report: &FileReport) -> Vec<Diagnostic> {
+    report
+        .findings
+        .iter()
+        .map(|finding| finding.diagnostic.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_output_json_envelope() {
+        let mut output = Output::new(OutputFormat::Json);
+        let diagnostics = vec![
+            Diagnostic {
+                rule: "similar-colors",
+                severity: Severity::Warning,
+                path: vec!["Materials".into(), "1".into()],
+                message: "Similar".to_string(),
+            },
+            Diagnostic {
+                rule: "color-format",
+                severity: Severity::Error,
+                path: vec!["Materials".into(), "7".into()],
+                message: "Not a color".to_string(),
+            },
+        ];
+        let content = "{\n  \"Materials\": [\n    {},\n    {}\n  ]\n}";
+        output.report(&FileReport::from_diagnostics(
+            Path::new("bus.obj.meta"),
+            content,
+            &diagnostics,
+        ));
+        output.set_result("variant_path", vec!["foo", "bar"]);
+
+        let envelope = output.to_json("validate", Some((1, "Validation failed".to_string())));
+        assert_eq!(envelope["version"], OUTPUT_VERSION);
+        assert_eq!(envelope["command"], "validate");
+        assert_eq!(envelope["success"], false);
+        assert_eq!(envelope["failure"]["exit_code"], 1);
+        assert_eq!(envelope["warnings"][0]["rule"], "similar-colors");
+        assert_eq!(envelope["warnings"][0]["line"], 4);
+        assert_eq!(envelope["errors"][0]["path"], json!(["Materials", "7"]));
+        assert_eq!(envelope["errors"][0]["line"], 2);
+        assert_eq!(envelope["result"]["variant_path"], json!(["foo", "bar"]));
+
+        let success = Output::new(OutputFormat::Json).to_json("schema", None);
+        assert_eq!(success["success"], true);
+        assert_eq!(success["failure"], Value::Null);
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let path: PathBuf = std::env::temp_dir().join("vt-utils-test-hash-file.txt");
+        fs::write(&path, "abc").expect("Should write file");
+
+        let written_file = hash_file(&path).expect("Should hash file");
+        assert_eq!(written_file.bytes, 3);
+        assert_eq!(
+            written_file.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        fs::remove_file(&path).expect("Should remove file");
+        assert!(hash_file(&path).is_none());
+    }
+
+    #[test]
+    fn test_buffered_output_merge() {
+        // Outer output is buffered too so the test prints nothing
+        let mut output = Output::new(OutputFormat::Text).buffered();
+        output.message("first");
+        let mut item_output = output.buffered();
+        item_output.message("second");
+        item_output.error_message("problem");
+        output.message("third");
+        output.merge(item_output);
+
+        let printed: Vec<String> = output
+            .buffer
+            .unwrap_or_default()
+            .into_iter()
+            .map(|text| match text {
+                BufferedText::Stdout(text) => format!("out {}", text.trim_end()),
+                BufferedText::Stderr(text) => format!("err {}", text.trim_end()),
+            })
+            .collect();
+        assert_eq!(
+            printed,
+            vec!["out first", "out third", "out second", "err problem"]
+        );
+    }
+}
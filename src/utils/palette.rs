@@ -1,8 +1,6 @@
-use crate::utils::variants::get_variant_path_from_meta;
-
 use super::{
     json_parse::{MaterialSchema, VTMetaSchema, VariantSchema},
-    variants::get_variant_from_meta,
+    variants::{find_variant_path, get_variant_by_path, VariantLookupError, VariantPath},
 };
 
 pub type VTPalette = Vec<MaterialSchema>;
@@ -11,29 +9,56 @@ pub type VTPalette = Vec<MaterialSchema>;
 pub enum GetPaletteError {
     VariantNotExist,
     FailedToBuildPalette,
+    MaterialIndexOutOfRange(u8),
+    AmbiguousVariant(Vec<VariantPath>),
 }
 
-fn swap_palette_materials_with_variant(palette: &mut VTPalette, variant: &VariantSchema) {
+impl From<VariantLookupError> for GetPaletteError {
+    fn from(e: VariantLookupError) -> Self {
+        match e {
+            VariantLookupError::VariantNotExist => GetPaletteError::VariantNotExist,
+            VariantLookupError::AmbiguousVariant(paths) => GetPaletteError::AmbiguousVariant(paths),
+        }
+    }
+}
+
+fn swap_palette_materials_with_variant(
+    palette: &mut VTPalette,
+    variant: &VariantSchema,
+) -> Result<(), GetPaletteError> {
     for (material_index, material) in variant.materials.iter() {
         let index: usize = *material_index as usize;
-        palette[index] = material.clone();
+        let slot = palette
+            .get_mut(index)
+            .ok_or(GetPaletteError::MaterialIndexOutOfRange(*material_index))?;
+        *slot = material.clone();
     }
+    Ok(())
 }
 
 pub fn get_palette_from_meta(
     meta: &VTMetaSchema,
     variant: &Option<String>,
+    ignore_case: bool,
+) -> Result<VTPalette, GetPaletteError> {
+    if let Some(variant_name) = variant {
+        let variant_path = find_variant_path(meta, variant_name, ignore_case)?;
+        return get_palette_from_variant_path(meta, &variant_path);
+    }
+
+    Ok(meta.materials.clone())
+}
+
+pub fn get_palette_from_variant_path(
+    meta: &VTMetaSchema,
+    variant_path: &VariantPath,
 ) -> Result<VTPalette, GetPaletteError> {
     let mut palette = meta.materials.clone();
 
-    if let Some(variant_name) = variant {
-        let variant_path = get_variant_path_from_meta(meta, variant_name)
-            .ok_or(GetPaletteError::VariantNotExist)?;
-        for variant_path_item in variant_path {
-            let variant_colors = get_variant_from_meta(meta, &variant_path_item)
-                .ok_or(GetPaletteError::FailedToBuildPalette)?;
-            swap_palette_materials_with_variant(&mut palette, variant_colors);
-        }
+    for depth in 1..=variant_path.len() {
+        let variant_colors = get_variant_by_path(meta, &variant_path[..depth].to_vec())
+            .ok_or(GetPaletteError::FailedToBuildPalette)?;
+        swap_palette_materials_with_variant(&mut palette, variant_colors)?;
     }
 
     Ok(palette)
@@ -41,7 +66,7 @@ pub fn get_palette_from_meta(
 
 #[cfg(test)]
 mod tests {
-    use crate::utils::json_parse::parse_material_json;
+    use crate::utils::{json_parse::parse_material_json, variants::get_variant_path_from_meta};
 
     use super::*;
 
@@ -95,7 +120,9 @@ mod tests {
         let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
 
         let search_variant = "Foo".to_string();
-        let variant = get_variant_from_meta(&meta, &search_variant).expect("Should get variant");
+        let variant_path =
+            get_variant_path_from_meta(&meta, &search_variant).expect("Should get variant path");
+        let variant = get_variant_by_path(&meta, &variant_path).expect("Should get variant");
 
         let index: u8 = 1;
         assert_eq!(
@@ -114,7 +141,7 @@ mod tests {
         let search_variant = "Foo".to_string();
 
         let palette =
-            get_palette_from_meta(&meta, &Some(search_variant)).expect("Should get palette");
+            get_palette_from_meta(&meta, &Some(search_variant), false).expect("Should get palette");
 
         assert_eq!(palette[0].color, "424242");
 
@@ -128,10 +155,38 @@ mod tests {
         let search_variant = "Lorem".to_string();
 
         let palette =
-            get_palette_from_meta(&meta, &Some(search_variant)).expect("Should get palette");
+            get_palette_from_meta(&meta, &Some(search_variant), false).expect("Should get palette");
 
         assert_eq!(palette[0].color, "424242");
         assert_eq!(palette[1].color, "00ff00");
         assert_eq!(palette[2].color, "0000ff");
     }
+
+    #[test]
+    fn test_get_palette_from_meta_variant_path() {
+        let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+
+        let palette = get_palette_from_meta(&meta, &Some("Bar/Lorem".to_string()), false)
+            .expect("Should get palette");
+        assert_eq!(palette[1].color, "00ff00");
+        assert_eq!(palette[2].color, "0000ff");
+
+        assert!(matches!(
+            get_palette_from_meta(&meta, &Some("Foo/Lorem".to_string()), false),
+            Err(GetPaletteError::VariantNotExist)
+        ));
+    }
+
+    #[test]
+    fn test_get_palette_from_meta_index_out_of_range() {
+        let mut meta =
+            parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+        meta.materials.truncate(2);
+        let search_variant = "Lorem".to_string();
+
+        assert!(matches!(
+            get_palette_from_meta(&meta, &Some(search_variant), false),
+            Err(GetPaletteError::MaterialIndexOutOfRange(2))
+        ));
+    }
 }
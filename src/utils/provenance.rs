@@ -0,0 +1,170 @@
+use super::{
+    get_colors_from_meta::MaterialProperty,
+    json_parse::{MaterialSchema, VTMetaSchema},
+    palette::GetPaletteError,
+    variants::{format_variant_path, get_variant_by_path, VariantPath},
+};
+
+/// Where the value of a material comes from: the base `Materials` list or a
+/// variant override.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MaterialSource {
+    Base,
+    Variant(VariantPath),
+}
+
+impl MaterialSource {
+    pub fn label(&self) -> String {
+        match self {
+            MaterialSource::Base => "Materials".to_string(),
+            MaterialSource::Variant(path) => format_variant_path(path),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MaterialOrigin {
+    pub source: MaterialSource,
+    pub material: MaterialSchema,
+}
+
+/// Every value a material index takes on the way from the base palette to the
+/// variant, the last one is the resolved value.
+#[derive(Debug)]
+pub struct MaterialProvenance {
+    pub index: usize,
+    pub chain: Vec<MaterialOrigin>,
+}
+
+impl MaterialProvenance {
+    pub fn resolved(&self) -> &MaterialOrigin {
+        // The chain always starts with the base material
+        &self.chain[self.chain.len() - 1]
+    }
+}
+
+/// Resolves the palette of `variant_path` like `get_palette_from_variant_path`
+/// but keeps the override chain of every material index.
+pub fn get_material_provenance(
+    meta: &VTMetaSchema,
+    variant_path: &VariantPath,
+) -> Result<Vec<MaterialProvenance>, GetPaletteError> {
+    let mut provenance: Vec<MaterialProvenance> = meta
+        .materials
+        .iter()
+        .enumerate()
+        .map(|(index, material)| MaterialProvenance {
+            index,
+            chain: vec![MaterialOrigin {
+                source: MaterialSource::Base,
+                material: material.clone(),
+            }],
+        })
+        .collect();
+
+    for depth in 1..=variant_path.len() {
+        let ancestor_path = variant_path[..depth].to_vec();
+        let variant = get_variant_by_path(meta, &ancestor_path)
+            .ok_or(GetPaletteError::FailedToBuildPalette)?;
+        for (material_index, material) in variant.materials.iter() {
+            let entry = provenance
+                .get_mut(*material_index as usize)
+                .ok_or(GetPaletteError::MaterialIndexOutOfRange(*material_index))?;
+            entry.chain.push(MaterialOrigin {
+                source: MaterialSource::Variant(ancestor_path.clone()),
+                material: material.clone(),
+            });
+        }
+    }
+
+    Ok(provenance)
+}
+
+pub fn format_property_value(material: &MaterialSchema, property: &MaterialProperty) -> String {
+    match property {
+        MaterialProperty::Color => material.color.clone(),
+        MaterialProperty::CompanyTint => material.company_tint.to_string(),
+        MaterialProperty::Emission => material.emission.to_string(),
+        MaterialProperty::Glassiness => material.glassiness.to_string(),
+        MaterialProperty::Smoothness => material.smoothness.to_string(),
+        MaterialProperty::Specular => material.specular.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{json_parse::parse_material_json, palette::get_palette_from_variant_path};
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        let material = |color: &str| {
+            format!(
+                r#"{{"Color": "{}","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}}"#,
+                color
+            )
+        };
+        let base: Vec<String> = (0..16).map(|_| material("424242")).collect();
+        format!(
+            r#"{{
+              "Materials": [{}],
+              "Variants": {{
+                "foo": {{
+                  "Materials": {{ "1": {}, "2": {} }},
+                  "Variants": {{
+                    "bar": {{ "Materials": {{ "2": {} }}, "Variants": null }}
+                  }}
+                }}
+              }}
+            }}"#,
+            base.join(","),
+            material("ff0000"),
+            material("00ff00"),
+            material("0000ff")
+        )
+    }
+
+    #[test]
+    fn test_get_material_provenance() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let variant_path = vec!["foo".to_string(), "bar".to_string()];
+
+        let provenance = get_material_provenance(&meta, &variant_path).expect("Should resolve");
+        assert_eq!(provenance.len(), 16);
+
+        assert_eq!(provenance[0].chain.len(), 1);
+        assert_eq!(provenance[0].resolved().source, MaterialSource::Base);
+
+        assert_eq!(
+            provenance[1].resolved().source,
+            MaterialSource::Variant(vec!["foo".to_string()])
+        );
+
+        let sources: Vec<String> = provenance[2]
+            .chain
+            .iter()
+            .map(|origin| origin.source.label())
+            .collect();
+        assert_eq!(sources, vec!["Materials", "foo", "foo/bar"]);
+        assert_eq!(
+            format_property_value(&provenance[2].resolved().material, &MaterialProperty::Color),
+            "0000ff"
+        );
+
+        let palette = get_palette_from_variant_path(&meta, &variant_path).expect("Should resolve");
+        for entry in provenance.iter() {
+            assert_eq!(entry.resolved().material.color, palette[entry.index].color);
+        }
+    }
+
+    #[test]
+    fn test_get_material_provenance_index_out_of_range() {
+        let mut meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        meta.materials.truncate(2);
+
+        assert!(matches!(
+            get_material_provenance(&meta, &vec!["foo".to_string(), "bar".to_string()]),
+            Err(GetPaletteError::MaterialIndexOutOfRange(2))
+        ));
+    }
+}
@@ -0,0 +1,377 @@
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    diagnostic::{format_json_path, Diagnostic, Severity},
+    json_locate::locate_json_path,
+    json_parse::VTMetaReadError,
+    lint::LINT_RULES,
+};
+
+pub const FILE_READ: &str = "file-read";
+pub const PARSE_ERROR: &str = "parse-error";
+pub const FILE_WRITE: &str = "file-write";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Diagnostic with its position in the .meta source. Line and column are
+/// 1-based, 0 when the position is unknown.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub diagnostic: Diagnostic,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileReport {
+    pub file_path: PathBuf,
+    pub findings: Vec<Finding>,
+}
+
+impl FileReport {
+    pub fn from_diagnostics(
+        file_path: &Path,
+        file_content: &str,
+        diagnostics: &[Diagnostic],
+    ) -> Self {
+        let findings = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let (line, column) = locate_json_path(file_content, &diagnostic.path);
+                Finding {
+                    diagnostic: diagnostic.clone(),
+                    line,
+                    column,
+                }
+            })
+            .collect();
+        FileReport {
+            file_path: file_path.to_path_buf(),
+            findings,
+        }
+    }
+
+    fn file_error(file_path: &Path, rule: &'static str, message: &str) -> Self {
+        FileReport {
+            file_path: file_path.to_path_buf(),
+            findings: vec![Finding {
+                diagnostic: Diagnostic {
+                    rule,
+                    severity: Severity::Error,
+                    path: Vec::new(),
+                    message: message.to_string(),
+                },
+                line: 0,
+                column: 0,
+            }],
+        }
+    }
+
+    pub fn read_error(file_path: &Path) -> Self {
+        FileReport::file_error(file_path, FILE_READ, "Failed to read the file")
+    }
+
+    pub fn write_error(file_path: &Path) -> Self {
+        FileReport::file_error(file_path, FILE_WRITE, "Failed to write the file")
+    }
+
+    pub fn parse_error(file_path: &Path, error: &VTMetaReadError) -> Self {
+        let VTMetaReadError::ParseError(parse_diagnostic) = error;
+        FileReport {
+            file_path: file_path.to_path_buf(),
+            findings: vec![Finding {
+                diagnostic: Diagnostic {
+                    rule: PARSE_ERROR,
+                    severity: Severity::Error,
+                    path: parse_diagnostic.path.clone(),
+                    message: parse_diagnostic.message.clone(),
+                },
+                line: parse_diagnostic.line,
+                column: parse_diagnostic.column,
+            }],
+        }
+    }
+}
+
+fn report_uri(file_path: &Path) -> String {
+    let uri = file_path.to_string_lossy().replace('\\', "/");
+    match uri.strip_prefix("./") {
+        Some(stripped) => stripped.to_string(),
+        None => uri,
+    }
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Warning => "warning",
+        Severity::Error => "error",
+    }
+}
+
+fn sarif_rule(rule: &str) -> Value {
+    match LINT_RULES.iter().find(|lint_rule| lint_rule.id == rule) {
+        Some(lint_rule) => json!({
+            "id": rule,
+            "shortDescription": { "text": lint_rule.description },
+        }),
+        None => json!({ "id": rule }),
+    }
+}
+
+fn sarif_result(file_path: &Path, finding: &Finding) -> Value {
+    let mut physical_location = json!({
+        "artifactLocation": { "uri": report_uri(file_path) },
+    });
+    if finding.line > 0 {
+        physical_location["region"] = json!({
+            "startLine": finding.line,
+            "startColumn": finding.column.max(1),
+        });
+    }
+
+    let mut result = json!({
+        "ruleId": finding.diagnostic.rule,
+        "level": sarif_level(finding.diagnostic.severity),
+        "message": { "text": finding.diagnostic.message },
+        "locations": [{ "physicalLocation": physical_location }],
+    });
+    if !finding.diagnostic.path.is_empty() {
+        result["locations"][0]["logicalLocations"] =
+            json!([{ "fullyQualifiedName": format_json_path(&finding.diagnostic.path) }]);
+    }
+    result
+}
+
+/// SARIF 2.1.0 log with a single run, understood by GitHub code scanning.
+pub fn sarif_report(reports: &[FileReport]) -> String {
+    let mut rules: Vec<&str> = Vec::new();
+    let mut results: Vec<Value> = Vec::new();
+    for report in reports {
+        for finding in report.findings.iter() {
+            if !rules.contains(&finding.diagnostic.rule) {
+                rules.push(finding.diagnostic.rule);
+            }
+            results.push(sarif_result(&report.file_path, finding));
+        }
+    }
+
+    let sarif = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "vt-utils",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules.iter().map(|rule| sarif_rule(rule)).collect::<Vec<Value>>(),
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&sarif).unwrap_or_default()
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn finding_location(file_path: &Path, finding: &Finding) -> String {
+    if finding.line == 0 {
+        return report_uri(file_path);
+    }
+    format!(
+        "{}:{}:{}",
+        report_uri(file_path),
+        finding.line,
+        finding.column
+    )
+}
+
+/// JUnit XML with a test suite per file. Every finding is a test case, errors
+/// are failures and warnings pass with the finding in `system-out`. Files
+/// without findings get a single passing test case.
+pub fn junit_report(name: &str, reports: &[FileReport]) -> String {
+    let total_tests: usize = reports
+        .iter()
+        .map(|report| report.findings.len().max(1))
+        .sum();
+    let total_failures: usize = reports
+        .iter()
+        .flat_map(|report| report.findings.iter())
+        .filter(|finding| finding.diagnostic.severity == Severity::Error)
+        .count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        escape_xml(name),
+        total_tests,
+        total_failures
+    ));
+
+    for report in reports {
+        let file_name = escape_xml(&report_uri(&report.file_path));
+        let failures = report
+            .findings
+            .iter()
+            .filter(|finding| finding.diagnostic.severity == Severity::Error)
+            .count();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            file_name,
+            report.findings.len().max(1),
+            failures
+        ));
+
+        if report.findings.is_empty() {
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\"/>\n",
+                escape_xml(name),
+                file_name
+            ));
+        }
+        for finding in report.findings.iter() {
+            let diagnostic = &finding.diagnostic;
+            xml.push_str(&format!(
+                "    <testcase name=\"{}\" classname=\"{}\">\n",
+                escape_xml(&format!(
+                    "{} {}",
+                    diagnostic.rule,
+                    format_json_path(&diagnostic.path)
+                )),
+                file_name
+            ));
+            let details = escape_xml(&format!(
+                "{}: {}",
+                finding_location(&report.file_path, finding),
+                diagnostic
+            ));
+            match diagnostic.severity {
+                Severity::Error => xml.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    diagnostic.rule,
+                    escape_xml(&diagnostic.message),
+                    details
+                )),
+                Severity::Warning => {
+                    xml.push_str(&format!("      <system-out>{}</system-out>\n", details))
+                }
+            }
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Writes the requested report files, `name` is used for the JUnit test suites.
+pub fn write_reports(
+    name: &str,
+    reports: &[FileReport],
+    sarif_file: &Option<PathBuf>,
+    junit_file: &Option<PathBuf>,
+) -> std::io::Result<()> {
+    if let Some(sarif_file) = sarif_file {
+        fs::write(sarif_file, sarif_report(reports))?;
+    }
+    if let Some(junit_file) = junit_file {
+        fs::write(junit_file, junit_report(name, reports))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_reports() -> Vec<FileReport> {
+        let content = "{\n  \"Materials\": [\n    {\"Color\": \"4242\"}\n  ]\n}";
+        let diagnostics = vec![
+            Diagnostic {
+                rule: "color-format",
+                severity: Severity::Error,
+                path: vec!["Materials".into(), "0".into(), "Color".into()],
+                message: "\"4242\" is not a <color>".to_string(),
+            },
+            Diagnostic {
+                rule: "similar-colors",
+                severity: Severity::Warning,
+                path: vec!["Materials".into(), "0".into()],
+                message: "Similar".to_string(),
+            },
+        ];
+        vec![
+            FileReport::from_diagnostics(Path::new("./mod/bus.obj.meta"), content, &diagnostics),
+            FileReport::read_error(Path::new("mod/missing.obj.meta")),
+            FileReport::from_diagnostics(Path::new("mod/ok.obj.meta"), content, &[]),
+        ]
+    }
+
+    #[test]
+    fn test_sarif_report() {
+        let sarif: Value =
+            serde_json::from_str(&sarif_report(&get_test_reports())).expect("Should be JSON");
+        let run = &sarif["runs"][0];
+
+        let rules: Vec<&str> = run["tool"]["driver"]["rules"]
+            .as_array()
+            .expect("Should have rules")
+            .iter()
+            .map(|rule| rule["id"].as_str().unwrap_or_default())
+            .collect();
+        assert_eq!(rules, vec!["color-format", "similar-colors", FILE_READ]);
+
+        let result = &run["results"][0];
+        assert_eq!(result["ruleId"], "color-format");
+        assert_eq!(result["level"], "error");
+        let location = &result["locations"][0];
+        assert_eq!(
+            location["physicalLocation"]["artifactLocation"]["uri"],
+            "mod/bus.obj.meta"
+        );
+        assert_eq!(location["physicalLocation"]["region"]["startLine"], 3);
+        assert_eq!(location["physicalLocation"]["region"]["startColumn"], 6);
+        assert_eq!(
+            location["logicalLocations"][0]["fullyQualifiedName"],
+            "Materials.0.Color"
+        );
+
+        assert_eq!(run["results"][1]["level"], "warning");
+        assert!(run["results"][2]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+
+    #[test]
+    fn test_junit_report() {
+        let junit = junit_report("vt-utils validate", &get_test_reports());
+
+        assert!(
+            junit.contains("<testsuites name=\"vt-utils validate\" tests=\"4\" failures=\"2\">")
+        );
+        assert!(junit.contains("<testsuite name=\"mod/bus.obj.meta\" tests=\"2\" failures=\"1\">"));
+        assert!(junit.contains("message=\"&quot;4242&quot; is not a &lt;color&gt;\""));
+        assert!(junit.contains("<system-out>mod/bus.obj.meta:3:5: warning[similar-colors]"));
+        assert!(
+            junit.contains("<testcase name=\"vt-utils validate\" classname=\"mod/ok.obj.meta\"/>")
+        );
+    }
+}
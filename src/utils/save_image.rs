@@ -1,6 +1,9 @@
-use std::path::PathBuf;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use image::{imageops, ImageBuffer, Rgb};
+use image::{ImageBuffer, Rgb, RgbImage};
 use imageproc::drawing::draw_filled_rect_mut;
 use imageproc::rect::Rect;
 
@@ -17,47 +20,73 @@ pub enum SaveImageError {
 
 pub type Colors = Vec<[u8; 3]>;
 
-pub fn save_image(
-    colors: &Colors,
-    output_path: &mut PathBuf,
-    filename: &str,
-) -> Result<SaveImageSuccess, SaveImageError> {
-    if colors.len() != 16 && colors.len() != 64 {
-        return Err(SaveImageError::InvalidInputLength);
-    }
-    let tile_size = 16;
+pub const TILE_SIZE: u32 = 16;
 
-    let size = if colors.len() <= 16 {
-        tile_size * 4
-    } else {
-        tile_size * 8
+/// Material indexes of the palette grid, rows listed from the top of the
+/// image. Materials fill the rows from the bottom left, so material 0 is in
+/// the bottom row like in the game textures.
+pub fn get_palette_grid(colors_count: usize) -> Result<Vec<Vec<usize>>, SaveImageError> {
+    let columns = match colors_count {
+        16 => 4,
+        64 => 8,
+        _ => return Err(SaveImageError::InvalidInputLength),
     };
 
-    let mut img: ImageBuffer<Rgb<u8>, Vec<u8>> = ImageBuffer::new(size, size);
+    let indexes: Vec<usize> = (0..colors_count).collect();
+    Ok(indexes
+        .chunks(columns)
+        .rev()
+        .map(|row| row.to_vec())
+        .collect())
+}
 
-    let mut start_x = 0;
-    let mut start_y = 0;
+pub fn build_palette_image(colors: &Colors, tile_size: u32) -> Result<RgbImage, SaveImageError> {
+    let grid = get_palette_grid(colors.len())?;
+    let size = tile_size * grid.len() as u32;
+    let mut img: RgbImage = ImageBuffer::new(size, size);
 
-    for color in colors.iter() {
-        draw_filled_rect_mut(
-            &mut img,
-            Rect::at(start_x, start_y).of_size(tile_size, tile_size),
-            Rgb(*color),
-        );
-        start_x += tile_size as i32;
-        if start_x >= size as i32 {
-            start_x = 0;
-            start_y += tile_size as i32;
+    for (row, indexes) in grid.iter().enumerate() {
+        for (column, index) in indexes.iter().enumerate() {
+            draw_filled_rect_mut(
+                &mut img,
+                Rect::at(
+                    (column as u32 * tile_size) as i32,
+                    (row as u32 * tile_size) as i32,
+                )
+                .of_size(tile_size, tile_size),
+                Rgb(colors[*index]),
+            );
         }
     }
 
-    let corrected_orientation_img = imageops::flip_vertical(&img);
+    Ok(img)
+}
 
+pub fn save_image(
+    colors: &Colors,
+    output_path: &mut PathBuf,
+    filename: &str,
+) -> Result<SaveImageSuccess, SaveImageError> {
+    let img = build_palette_image(colors, TILE_SIZE)?;
+    save_rgb_image(&img, output_path, filename)
+}
+
+pub fn save_rgb_image(
+    img: &RgbImage,
+    output_path: &mut PathBuf,
+    filename: &str,
+) -> Result<SaveImageSuccess, SaveImageError> {
     output_path.push(filename);
+    // Names with subdirectories, e.g. from --name-template
+    if Path::new(filename).components().count() > 1 {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent).map_err(|_| SaveImageError::SaveError)?;
+        }
+    }
 
     let str_output_path: &str = &output_path.to_string_lossy();
 
-    match corrected_orientation_img.save(&output_path) {
+    match img.save(&output_path) {
         Ok(()) => Ok(SaveImageSuccess::SaveOk(format!(
             "Succesfully saved {}",
             str_output_path
@@ -65,3 +94,36 @@ pub fn save_image(
         Err(_) => Err(SaveImageError::SaveError),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_palette_grid() {
+        let grid = get_palette_grid(16).expect("Should build grid");
+        assert_eq!(
+            grid,
+            vec![
+                vec![12, 13, 14, 15],
+                vec![8, 9, 10, 11],
+                vec![4, 5, 6, 7],
+                vec![0, 1, 2, 3],
+            ]
+        );
+        assert_eq!(get_palette_grid(64).expect("Should build grid").len(), 8);
+        assert!(get_palette_grid(20).is_err());
+    }
+
+    #[test]
+    fn test_build_palette_image() {
+        let colors: Colors = (0..16).map(|index| [index as u8, 0, 0]).collect();
+        let img = build_palette_image(&colors, TILE_SIZE).expect("Should build image");
+
+        assert_eq!(img.dimensions(), (64, 64));
+        assert_eq!(img.get_pixel(0, 63), &Rgb([0, 0, 0]));
+        assert_eq!(img.get_pixel(63, 63), &Rgb([3, 0, 0]));
+        assert_eq!(img.get_pixel(0, 0), &Rgb([12, 0, 0]));
+        assert_eq!(img.get_pixel(63, 0), &Rgb([15, 0, 0]));
+    }
+}
@@ -0,0 +1,230 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{ImageFormat, RgbImage};
+use std::io::Cursor;
+
+use super::save_image::Colors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsProtocol {
+    Blocks,
+    Kitty,
+    Sixel,
+}
+
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Guesses graphics support from terminal identification, which unlike
+/// terminal queries also works over SSH. Unknown terminals get truecolor blocks.
+pub fn detect_graphics_protocol(term: &str, term_program: &str) -> GraphicsProtocol {
+    let term = term.to_lowercase();
+    let term_program = term_program.to_lowercase();
+
+    if term.contains("kitty") || term.contains("ghostty") || term_program == "wezterm" {
+        return GraphicsProtocol::Kitty;
+    }
+    if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+        return GraphicsProtocol::Sixel;
+    }
+    GraphicsProtocol::Blocks
+}
+
+/// Whether dark text is more readable than light text on `color`.
+pub fn is_light_color(color: &[u8; 3]) -> bool {
+    0.299 * color[0] as f64 + 0.587 * color[1] as f64 + 0.114 * color[2] as f64 > 128.0
+}
+
+fn label_color(color: &[u8; 3]) -> &'static str {
+    if is_light_color(color) {
+        "30"
+    } else {
+        "97"
+    }
+}
+
+/// Every material is a 4x2 cell block, roughly square in most terminal fonts.
+/// With `labels` the material index is written into the cell.
+pub fn render_blocks(colors: &Colors, grid: &[Vec<usize>], labels: bool) -> String {
+    let mut text = String::new();
+    for row in grid {
+        for line in 0..2 {
+            for index in row {
+                let [r, g, b] = colors[*index];
+                let cell = if labels && line == 0 {
+                    format!("{:>3} ", index)
+                } else {
+                    "    ".to_string()
+                };
+                text.push_str(&format!(
+                    "\x1b[48;2;{};{};{};{}m{}",
+                    r,
+                    g,
+                    b,
+                    label_color(&colors[*index]),
+                    cell
+                ));
+            }
+            text.push_str("\x1b[0m\n");
+        }
+    }
+    text
+}
+
+/// Kitty graphics protocol, the PNG is scaled by the terminal to
+/// `columns` x `rows` cells.
+pub fn render_kitty(image: &RgbImage, columns: usize, rows: usize) -> String {
+    let mut png: Vec<u8> = Vec::new();
+    if image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .is_err()
+    {
+        return String::new();
+    }
+    let encoded = STANDARD.encode(png);
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+
+    let mut text = String::new();
+    for (position, chunk) in chunks.iter().enumerate() {
+        let more = if position + 1 < chunks.len() { 1 } else { 0 };
+        let control = if position == 0 {
+            format!("a=T,f=100,c={},r={},m={}", columns, rows, more)
+        } else {
+            format!("m={}", more)
+        };
+        text.push_str(&format!(
+            "\x1b_G{};{}\x1b\\",
+            control,
+            String::from_utf8_lossy(chunk)
+        ));
+    }
+    text.push('\n');
+    text
+}
+
+fn push_sixel_run(text: &mut String, sixel: char, count: usize) {
+    match count {
+        0 => {}
+        1..=3 => text.extend(std::iter::repeat_n(sixel, count)),
+        _ => text.push_str(&format!("!{}{}", count, sixel)),
+    }
+}
+
+/// Sixel graphics with a color register for every distinct palette color.
+pub fn render_sixel(image: &RgbImage) -> String {
+    let (width, height) = image.dimensions();
+    let mut registers: Vec<[u8; 3]> = Vec::new();
+    for pixel in image.pixels() {
+        if !registers.contains(&pixel.0) {
+            registers.push(pixel.0);
+        }
+    }
+
+    let mut text = format!("\x1bPq\"1;1;{};{}", width, height);
+    for (register, [r, g, b]) in registers.iter().enumerate() {
+        let percent = |channel: &u8| (*channel as u32 * 100 + 127) / 255;
+        text.push_str(&format!(
+            "#{};2;{};{};{}",
+            register,
+            percent(r),
+            percent(g),
+            percent(b)
+        ));
+    }
+
+    for band in (0..height).step_by(6) {
+        for (register, color) in registers.iter().enumerate() {
+            let sixels: Vec<char> = (0..width)
+                .map(|x| {
+                    let bits = (0..6)
+                        .filter(|dy| {
+                            band + dy < height && &image.get_pixel(x, band + dy).0 == color
+                        })
+                        .fold(0u8, |bits, dy| bits | (1 << dy));
+                    (63 + bits) as char
+                })
+                .collect();
+            if sixels.iter().all(|sixel| *sixel == '?') {
+                continue;
+            }
+
+            text.push_str(&format!("#{}", register));
+            let mut run_char = sixels[0];
+            let mut run_length = 0;
+            for sixel in sixels {
+                if sixel != run_char {
+                    push_sixel_run(&mut text, run_char, run_length);
+                    run_char = sixel;
+                    run_length = 0;
+                }
+                run_length += 1;
+            }
+            push_sixel_run(&mut text, run_char, run_length);
+            text.push('$');
+        }
+        text.push('-');
+    }
+
+    text.push_str("\x1b\\\n");
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn test_detect_graphics_protocol() {
+        assert_eq!(
+            detect_graphics_protocol("xterm-kitty", ""),
+            GraphicsProtocol::Kitty
+        );
+        assert_eq!(
+            detect_graphics_protocol("xterm-256color", "WezTerm"),
+            GraphicsProtocol::Kitty
+        );
+        assert_eq!(
+            detect_graphics_protocol("foot-extra", ""),
+            GraphicsProtocol::Sixel
+        );
+        assert_eq!(
+            detect_graphics_protocol("xterm-256color", "Apple_Terminal"),
+            GraphicsProtocol::Blocks
+        );
+    }
+
+    #[test]
+    fn test_render_blocks() {
+        let colors: Colors = vec![[255, 255, 255], [0, 0, 0]];
+        let grid = vec![vec![1], vec![0]];
+
+        assert_eq!(
+            render_blocks(&colors, &grid, true),
+            "\x1b[48;2;0;0;0;97m  1 \x1b[0m\n\
+             \x1b[48;2;0;0;0;97m    \x1b[0m\n\
+             \x1b[48;2;255;255;255;30m  0 \x1b[0m\n\
+             \x1b[48;2;255;255;255;30m    \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_render_kitty() {
+        let image = RgbImage::from_pixel(2, 2, Rgb([255, 0, 0]));
+        let text = render_kitty(&image, 4, 2);
+
+        assert!(text.starts_with("\x1b_Ga=T,f=100,c=4,r=2,m=0;iVBORw0KGgo"));
+        assert!(text.ends_with("\x1b\\\n"));
+    }
+
+    #[test]
+    fn test_render_sixel() {
+        let mut image = RgbImage::from_pixel(5, 7, Rgb([255, 0, 0]));
+        image.put_pixel(4, 0, Rgb([0, 0, 255]));
+
+        assert_eq!(
+            render_sixel(&image),
+            "\x1bPq\"1;1;5;7#0;2;100;0;0#1;2;0;0;100\
+             #0!4~}$#1!4?@$-#0!5@$-\x1b\\\n"
+        );
+    }
+}
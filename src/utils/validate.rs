@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use super::{
+    diagnostic::{Diagnostic, JsonPath, Severity},
+    json_parse::{MaterialSchema, VTMetaSchema, Variants},
+    variants::{format_variant_path, variant_json_path, VariantPath},
+};
+
+pub const PALETTE_SIZE: &str = "palette-size";
+pub const MATERIAL_INDEX: &str = "material-index";
+pub const COLOR_FORMAT: &str = "color-format";
+pub const DUPLICATE_VARIANT: &str = "duplicate-variant";
+pub const EMPTY_VARIANT: &str = "empty-variant";
+
+pub const VALIDATION_RULES: [&str; 5] = [
+    PALETTE_SIZE,
+    MATERIAL_INDEX,
+    COLOR_FORMAT,
+    DUPLICATE_VARIANT,
+    EMPTY_VARIANT,
+];
+
+fn is_valid_color(color: &str) -> bool {
+    color.len() == 6 && color.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn check_color(material: &MaterialSchema, path: &JsonPath, diagnostics: &mut Vec<Diagnostic>) {
+    if !is_valid_color(&material.color) {
+        let mut color_path = path.clone();
+        color_path.push("Color".to_string());
+        diagnostics.push(Diagnostic {
+            rule: COLOR_FORMAT,
+            severity: Severity::Error,
+            path: color_path,
+            message: format!(
+                "\"{}\" is not a color, expected 6 hex digits like \"424242\"",
+                material.color
+            ),
+        });
+    }
+}
+
+fn validate_variants(
+    variants: &Variants,
+    parent: &VariantPath,
+    palette_length: usize,
+    names: &mut HashMap<String, Vec<VariantPath>>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for (name, variant) in variants {
+        let mut variant_path = parent.clone();
+        variant_path.push(name.to_owned());
+        let json_path = variant_json_path(&variant_path);
+        names
+            .entry(name.to_owned())
+            .or_default()
+            .push(variant_path.clone());
+
+        let has_sub_variants = variant
+            .variants
+            .as_ref()
+            .is_some_and(|sub_variants| !sub_variants.is_empty());
+        if variant.materials.is_empty() && !has_sub_variants {
+            diagnostics.push(Diagnostic {
+                rule: EMPTY_VARIANT,
+                severity: Severity::Warning,
+                path: json_path.clone(),
+                message: format!("Variant {} does not override any material", name),
+            });
+        }
+
+        for (index, material) in variant.materials.iter() {
+            let mut material_path = json_path.clone();
+            material_path.push("Materials".to_string());
+            material_path.push(index.to_string());
+            if *index as usize >= palette_length {
+                diagnostics.push(Diagnostic {
+                    rule: MATERIAL_INDEX,
+                    severity: Severity::Error,
+                    path: material_path.clone(),
+                    message: format!(
+                        "Material index {} is out of range, palette has {} materials",
+                        index, palette_length
+                    ),
+                });
+            }
+            check_color(material, &material_path, diagnostics);
+        }
+
+        if let Some(sub_variants) = &variant.variants {
+            validate_variants(
+                sub_variants,
+                &variant_path,
+                palette_length,
+                names,
+                diagnostics,
+            );
+        }
+    }
+}
+
+pub fn validate_meta(meta: &VTMetaSchema) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let palette_length = meta.materials.len();
+
+    if palette_length != 16 && palette_length != 64 {
+        diagnostics.push(Diagnostic {
+            rule: PALETTE_SIZE,
+            severity: Severity::Error,
+            path: vec!["Materials".to_string()],
+            message: format!(
+                "Palette has {} materials, expected 16 or 64",
+                palette_length
+            ),
+        });
+    }
+
+    for (index, material) in meta.materials.iter().enumerate() {
+        let path = vec!["Materials".to_string(), index.to_string()];
+        check_color(material, &path, &mut diagnostics);
+    }
+
+    if let Some(variants) = &meta.variants {
+        let mut names: HashMap<String, Vec<VariantPath>> = HashMap::new();
+        validate_variants(
+            variants,
+            &Vec::new(),
+            palette_length,
+            &mut names,
+            &mut diagnostics,
+        );
+
+        let mut duplicated: Vec<(String, Vec<VariantPath>)> = names
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .collect();
+        duplicated.sort();
+        for (name, paths) in duplicated {
+            let listed_paths: Vec<String> = paths.iter().map(format_variant_path).collect();
+            diagnostics.push(Diagnostic {
+                rule: DUPLICATE_VARIANT,
+                severity: Severity::Warning,
+                path: variant_json_path(&paths[1]),
+                message: format!(
+                    "Variant name {} is used more than once: {}",
+                    name,
+                    listed_paths.join(", ")
+                ),
+            });
+        }
+    }
+
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{diagnostic::has_errors, json_parse::parse_material_json};
+
+    use super::*;
+
+    fn get_material(color: &str) -> String {
+        format!(
+            r#"{{"Color": "{}","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}}"#,
+            color
+        )
+    }
+
+    fn get_test_data(palette_length: usize, variants: &str) -> String {
+        let materials: Vec<String> = (0..palette_length)
+            .map(|_| get_material("424242"))
+            .collect();
+        format!(
+            r#"{{ "Materials": [{}], "Variants": {} }}"#,
+            materials.join(","),
+            variants
+        )
+    }
+
+    fn get_rules(diagnostics: &[Diagnostic]) -> Vec<&'static str> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.rule)
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_meta_valid() {
+        let meta = parse_material_json(&get_test_data(16, "null")).expect("Should create meta");
+        assert!(validate_meta(&meta).is_empty());
+
+        let meta = parse_material_json(&get_test_data(64, "null")).expect("Should create meta");
+        assert!(validate_meta(&meta).is_empty());
+    }
+
+    #[test]
+    fn test_validate_meta_palette_size() {
+        let meta = parse_material_json(&get_test_data(15, "null")).expect("Should create meta");
+        let diagnostics = validate_meta(&meta);
+
+        assert_eq!(get_rules(&diagnostics), vec![PALETTE_SIZE]);
+        assert!(has_errors(&diagnostics));
+    }
+
+    #[test]
+    fn test_validate_meta_material_index_and_color() {
+        let variants = format!(
+            r#"{{ "foo": {{ "Materials": {{ "16": {}, "3": {} }}, "Variants": null }} }}"#,
+            get_material("424242"),
+            get_material("42")
+        );
+        let meta = parse_material_json(&get_test_data(16, &variants)).expect("Should create meta");
+        let diagnostics = validate_meta(&meta);
+
+        assert_eq!(get_rules(&diagnostics), vec![MATERIAL_INDEX, COLOR_FORMAT]);
+        assert_eq!(
+            diagnostics[1].path,
+            vec!["Variants", "foo", "Materials", "3", "Color"]
+        );
+    }
+
+    #[test]
+    fn test_validate_meta_duplicated_and_empty_variants() {
+        let variants = r#"{
+            "foo": { "Materials": {}, "Variants": { "night": { "Materials": {}, "Variants": null } } },
+            "night": { "Materials": {}, "Variants": null }
+        }"#;
+        let meta = parse_material_json(&get_test_data(16, variants)).expect("Should create meta");
+        let diagnostics = validate_meta(&meta);
+
+        assert_eq!(
+            get_rules(&diagnostics),
+            vec![EMPTY_VARIANT, EMPTY_VARIANT, DUPLICATE_VARIANT]
+        );
+        assert!(!has_errors(&diagnostics));
+    }
+}
@@ -0,0 +1,299 @@
+use globset::{GlobBuilder, GlobMatcher};
+use indexmap::IndexMap;
+use regex::{Regex, RegexBuilder};
+
+use super::{
+    json_parse::VTMetaSchema,
+    variants::{
+        find_variant_path, format_variant_path, get_variant_paths_from_meta, VariantLookupError,
+        VariantPath, VARIANT_PATH_SEPARATOR,
+    },
+};
+
+/// Prefix of variant patterns matched as regular expressions
+pub const REGEX_PATTERN_PREFIX: &str = "re:";
+
+/// Warning about a `--variant` pattern which selected nothing
+pub const UNMATCHED_VARIANT: &str = "unmatched-variant";
+
+const GLOB_CHARS: [char; 4] = ['*', '?', '[', '{'];
+
+const SUBTREE_SUFFIX: &str = "/**";
+
+enum VariantPattern {
+    /// Name or path resolved like a single variant
+    Name(String),
+    Glob {
+        matcher: GlobMatcher,
+        /// Patterns with a separator match whole paths, others the last name
+        full_path: bool,
+        /// Root of a `foo/**` pattern, selected together with its descendants
+        subtree_root: Option<GlobMatcher>,
+    },
+    Regex {
+        regex: Regex,
+        /// Like globs, expressions with a separator match whole paths
+        full_path: bool,
+    },
+}
+
+#[derive(Debug)]
+pub enum SelectVariantsError {
+    /// Pattern and the reason it can't be parsed
+    InvalidPattern((String, String)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectedVariant {
+    pub path: VariantPath,
+    /// Name of the variant used in exported file names
+    pub name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct VariantSelection {
+    /// Matched variants without duplicates, in order of the first pattern
+    /// matching them and file order for a single pattern
+    pub variants: Vec<SelectedVariant>,
+    pub unmatched: Vec<String>,
+}
+
+/// True when `pattern` is a glob or regular expression rather than a name.
+pub fn is_variant_pattern(pattern: &str) -> bool {
+    pattern.starts_with(REGEX_PATTERN_PREFIX) || pattern.contains(GLOB_CHARS)
+}
+
+fn build_glob(pattern: &str, ignore_case: bool) -> Result<GlobMatcher, String> {
+    GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(ignore_case)
+        .build()
+        .map(|glob| glob.compile_matcher())
+        .map_err(|e| e.kind().to_string())
+}
+
+fn parse_pattern(pattern: &str, ignore_case: bool) -> Result<VariantPattern, SelectVariantsError> {
+    let invalid = |message: String| SelectVariantsError::InvalidPattern((pattern.into(), message));
+
+    if let Some(expression) = pattern.strip_prefix(REGEX_PATTERN_PREFIX) {
+        return RegexBuilder::new(expression)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(|regex| VariantPattern::Regex {
+                regex,
+                full_path: expression.contains(VARIANT_PATH_SEPARATOR),
+            })
+            .map_err(|e| invalid(e.to_string()));
+    }
+    if !is_variant_pattern(pattern) {
+        return Ok(VariantPattern::Name(pattern.to_string()));
+    }
+
+    let subtree_root = match pattern.strip_suffix(SUBTREE_SUFFIX) {
+        Some(root) if !root.is_empty() => Some(build_glob(root, ignore_case).map_err(invalid)?),
+        _ => None,
+    };
+    Ok(VariantPattern::Glob {
+        matcher: build_glob(pattern, ignore_case).map_err(invalid)?,
+        full_path: pattern.contains(VARIANT_PATH_SEPARATOR),
+        subtree_root,
+    })
+}
+
+impl VariantPattern {
+    fn matches(&self, variant_path: &VariantPath) -> bool {
+        let full_path = format_variant_path(variant_path);
+        let candidate = |match_full_path: bool| match match_full_path {
+            true => full_path.as_str(),
+            false => variant_path.last().map_or("", |name| name.as_str()),
+        };
+        match self {
+            VariantPattern::Name(_) => false,
+            VariantPattern::Regex {
+                regex,
+                full_path: match_full_path,
+            } => regex.is_match(candidate(*match_full_path)),
+            VariantPattern::Glob {
+                matcher,
+                full_path: match_full_path,
+                subtree_root,
+            } => {
+                matcher.is_match(candidate(*match_full_path))
+                    || subtree_root
+                        .as_ref()
+                        .is_some_and(|root| root.is_match(&full_path))
+            }
+        }
+    }
+}
+
+/// Name as written by the user, or as in the file when matched ignoring case.
+fn name_of_named_variant(name: &str, variant_path: &VariantPath, ignore_case: bool) -> String {
+    match (ignore_case, name.contains(VARIANT_PATH_SEPARATOR)) {
+        (false, _) => name.to_owned(),
+        (true, true) => format_variant_path(variant_path),
+        (true, false) => variant_path.last().cloned().unwrap_or_default(),
+    }
+}
+
+/// Last name of the variant, the full path when another variant has the same name.
+fn name_of_matched_variant(variant_path: &VariantPath, all_paths: &[VariantPath]) -> String {
+    let name = variant_path.last();
+    let is_unique = all_paths.iter().filter(|path| path.last() == name).count() == 1;
+    match (is_unique, name) {
+        (true, Some(name)) => name.to_owned(),
+        _ => format_variant_path(variant_path),
+    }
+}
+
+/// Resolves `--variant` values. A value is a name or path like
+/// `get_variant_path_from_meta` accepts, a glob like `winter*` matched against
+/// names or against full paths when it contains `/`, `foo/**` for `foo` with
+/// all its descendants, or a regular expression prefixed with `re:` searched
+/// in names, or in full paths when it contains `/`.
+pub fn select_variants(
+    meta: &VTMetaSchema,
+    patterns: &[String],
+    ignore_case: bool,
+) -> Result<VariantSelection, SelectVariantsError> {
+    let all_paths = get_variant_paths_from_meta(meta);
+    let mut selected: IndexMap<VariantPath, String> = IndexMap::new();
+    let mut unmatched: Vec<String> = Vec::new();
+
+    for pattern in patterns {
+        let matches: Vec<(VariantPath, String)> = match parse_pattern(pattern, ignore_case)? {
+            VariantPattern::Name(name) => match find_variant_path(meta, &name, ignore_case) {
+                Ok(path) => {
+                    let file_name = name_of_named_variant(&name, &path, ignore_case);
+                    vec![(path, file_name)]
+                }
+                Err(VariantLookupError::VariantNotExist) => Vec::new(),
+                Err(VariantLookupError::AmbiguousVariant(paths)) => {
+                    return Err(SelectVariantsError::AmbiguousVariant((name, paths)));
+                }
+            },
+            variant_pattern => all_paths
+                .iter()
+                .filter(|path| variant_pattern.matches(path))
+                .map(|path| (path.clone(), name_of_matched_variant(path, &all_paths)))
+                .collect(),
+        };
+        if matches.is_empty() {
+            unmatched.push(pattern.to_owned());
+        }
+        for (path, name) in matches {
+            selected.entry(path).or_insert(name);
+        }
+    }
+
+    Ok(VariantSelection {
+        variants: selected
+            .into_iter()
+            .map(|(path, name)| SelectedVariant { path, name })
+            .collect(),
+        unmatched,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::parse_material_json;
+
+    use super::*;
+
+    fn get_test_meta() -> VTMetaSchema {
+        let material = r#"{"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}"#;
+        let base: Vec<&str> = (0..16).map(|_| material).collect();
+        let leaf = r#"{ "Materials": {}, "Variants": null }"#;
+        let data = format!(
+            r#"{{
+              "Materials": [{}],
+              "Variants": {{
+                "winter": {{ "Materials": {{}}, "Variants": {{ "night": {leaf} }} }},
+                "winter_old": {leaf},
+                "summer": {{ "Materials": {{}}, "Variants": {{ "night": {leaf}, "dusk": {leaf} }} }}
+              }}
+            }}"#,
+            base.join(","),
+        );
+        parse_material_json(&data).expect("Should create meta")
+    }
+
+    fn select(patterns: &[&str], ignore_case: bool) -> VariantSelection {
+        let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+        select_variants(&get_test_meta(), &patterns, ignore_case).expect("Should select variants")
+    }
+
+    fn names(selection: &VariantSelection) -> Vec<&str> {
+        selection
+            .variants
+            .iter()
+            .map(|variant| variant.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_variants_glob_and_subtree() {
+        assert_eq!(
+            names(&select(&["winter*"], false)),
+            vec!["winter", "winter_old"]
+        );
+        assert_eq!(names(&select(&["d*"], false)), vec!["dusk"]);
+        assert_eq!(
+            names(&select(&["winter/**"], false)),
+            vec!["winter", "winter/night"]
+        );
+        assert_eq!(
+            names(&select(&["*/*"], false)),
+            vec!["winter/night", "summer/night", "dusk"]
+        );
+        assert!(select(&["WINTER*"], false).variants.is_empty());
+        assert_eq!(
+            names(&select(&["WINTER*"], true)),
+            vec!["winter", "winter_old"]
+        );
+    }
+
+    #[test]
+    fn test_select_variants_regex() {
+        let selection = select(&["re:night$"], false);
+        let paths: Vec<String> = selection
+            .variants
+            .iter()
+            .map(|variant| format_variant_path(&variant.path))
+            .collect();
+        assert_eq!(paths, vec!["winter/night", "summer/night"]);
+        assert_eq!(names(&select(&["re:^winter_"], false)), vec!["winter_old"]);
+
+        // Matched against names like globs, unless the expression has a `/`
+        assert_eq!(names(&select(&["re:^d"], false)), vec!["dusk"]);
+        assert_eq!(names(&select(&["d*"], false)), vec!["dusk"]);
+        assert_eq!(
+            names(&select(&["re:^summer/"], false)),
+            vec!["summer/night", "dusk"]
+        );
+    }
+
+    #[test]
+    fn test_select_variants_multiple_and_unmatched() {
+        let selection = select(&["summer", "s*", "autumn*", "spring", "Summer/dusk"], true);
+        assert_eq!(names(&selection), vec!["summer", "summer/dusk"]);
+        assert_eq!(selection.unmatched, vec!["autumn*", "spring"]);
+    }
+
+    #[test]
+    fn test_select_variants_errors() {
+        let meta = get_test_meta();
+        for pattern in ["re:(", "winter[", "{a"] {
+            assert!(matches!(
+                select_variants(&meta, &[pattern.to_string()], false),
+                Err(SelectVariantsError::InvalidPattern(_))
+            ));
+        }
+        assert!(matches!(
+            select_variants(&meta, &["night".to_string()], false),
+            Err(SelectVariantsError::AmbiguousVariant(_))
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+use super::{
+    json_parse::{VTMetaSchema, Variants},
+    palette::get_palette_from_variant_path,
+    variants::{
+        get_variant_by_path, get_variant_by_path_mut, get_variant_path_from_meta,
+        suggest_variant_paths, VariantLookupError, VariantPath,
+    },
+};
+
+#[derive(Debug)]
+pub enum VariantTransferError {
+    /// Name with the closest existing variants
+    VariantNotExist((String, Vec<VariantPath>)),
+    AmbiguousVariant((String, Vec<VariantPath>)),
+    VariantAlreadyExist(String),
+    EmptySnippet,
+    InvalidMaterialIndexes(Vec<(VariantPath, u8)>),
+}
+
+fn variant_not_exist(meta: &VTMetaSchema, variant_name: &str) -> VariantTransferError {
+    VariantTransferError::VariantNotExist((
+        variant_name.to_owned(),
+        suggest_variant_paths(meta, variant_name),
+    ))
+}
+
+fn lookup_error(
+    meta: &VTMetaSchema,
+    variant_name: &str,
+    e: VariantLookupError,
+) -> VariantTransferError {
+    match e {
+        VariantLookupError::VariantNotExist => variant_not_exist(meta, variant_name),
+        VariantLookupError::AmbiguousVariant(paths) => {
+            VariantTransferError::AmbiguousVariant((variant_name.to_owned(), paths))
+        }
+    }
+}
+
+/// Returns a single entry `Variants` map holding the variant subtree. With
+/// `flatten` the overrides of all ancestors are baked into the extracted
+/// variant, so it renders the same when injected at the top level.
+pub fn extract_variant(
+    meta: &VTMetaSchema,
+    variant_name: &str,
+    flatten: bool,
+) -> Result<Variants, VariantTransferError> {
+    let not_exist = || variant_not_exist(meta, variant_name);
+    let variant_path = get_variant_path_from_meta(meta, variant_name)
+        .map_err(|e| lookup_error(meta, variant_name, e))?;
+    let mut variant = get_variant_by_path(meta, &variant_path)
+        .ok_or_else(not_exist)?
+        .clone();
+
+    if flatten && variant_path.len() > 1 {
+        let parent_path = variant_path[..variant_path.len() - 1].to_vec();
+        let parent_palette =
+            get_palette_from_variant_path(meta, &parent_path).map_err(|_| not_exist())?;
+        let own_materials = std::mem::take(&mut variant.materials);
+
+        for (index, material) in parent_palette.into_iter().enumerate() {
+            let key = index as u8;
+            if let Some(own_material) = own_materials.get(&key) {
+                variant.materials.insert(key, own_material.clone());
+            } else if meta.materials[index] != material {
+                variant.materials.insert(key, material);
+            }
+        }
+    }
+
+    let mut snippet = Variants::new();
+    let name = variant_path.last().ok_or_else(not_exist)?;
+    snippet.insert(name.to_owned(), variant);
+    Ok(snippet)
+}
+
+fn collect_invalid_indexes(
+    variants: &Variants,
+    parent: &VariantPath,
+    palette_length: usize,
+    invalid: &mut Vec<(VariantPath, u8)>,
+) {
+    for (name, variant) in variants {
+        let mut path = parent.clone();
+        path.push(name.to_owned());
+        for index in variant.materials.keys() {
+            if *index as usize >= palette_length {
+                invalid.push((path.clone(), *index));
+            }
+        }
+        if let Some(sub_variants) = &variant.variants {
+            collect_invalid_indexes(sub_variants, &path, palette_length, invalid);
+        }
+    }
+}
+
+/// Grafts every variant of the snippet into the target. Variants are placed at
+/// the top level, or under `parent` when given.
+pub fn inject_variants(
+    meta: &mut VTMetaSchema,
+    snippet: Variants,
+    parent: &Option<String>,
+    replace: bool,
+) -> Result<(), VariantTransferError> {
+    if snippet.is_empty() {
+        return Err(VariantTransferError::EmptySnippet);
+    }
+
+    let mut invalid: Vec<(VariantPath, u8)> = Vec::new();
+    collect_invalid_indexes(&snippet, &Vec::new(), meta.materials.len(), &mut invalid);
+    if !invalid.is_empty() {
+        return Err(VariantTransferError::InvalidMaterialIndexes(invalid));
+    }
+
+    let destination = if let Some(parent_name) = parent {
+        let parent_path = get_variant_path_from_meta(meta, parent_name)
+            .map_err(|e| lookup_error(meta, parent_name, e))?;
+        let parent_variant = get_variant_by_path_mut(meta, &parent_path).ok_or(
+            VariantTransferError::VariantNotExist((parent_name.to_owned(), Vec::new())),
+        )?;
+        parent_variant.variants.get_or_insert_with(Variants::new)
+    } else {
+        meta.variants.get_or_insert_with(Variants::new)
+    };
+
+    if !replace {
+        if let Some(name) = snippet.keys().find(|name| destination.contains_key(*name)) {
+            return Err(VariantTransferError::VariantAlreadyExist(name.to_owned()));
+        }
+    }
+
+    destination.extend(snippet);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::{parse_material_json, parse_variants_json};
+
+    use super::*;
+
+    fn get_test_data() -> String {
+        r#"
+        {
+          "Materials": [
+            {"Color": "424242","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffa500","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+            {"Color": "ffa500","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+          ],
+          "Variants": {
+            "Foo": {
+              "Materials": {
+                "1": {"Color": "ff0000","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+              },
+              "Variants": {
+                "Bar": {
+                  "Materials": {
+                    "2": {"Color": "0000ff","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+                  },
+                  "Variants": null
+                }
+              }
+            }
+          }
+        }"#
+        .to_string()
+    }
+
+    #[test]
+    fn test_extract_variant() {
+        let meta = parse_material_json(&get_test_data()).expect("Should create meta");
+
+        let snippet = extract_variant(&meta, "Bar", false).expect("Should extract");
+        let variant = snippet.get("Bar").expect("Should contain variant");
+        assert_eq!(variant.materials.len(), 1);
+
+        let flattened = extract_variant(&meta, "Bar", true).expect("Should extract");
+        let variant = flattened.get("Bar").expect("Should contain variant");
+        assert_eq!(variant.materials.len(), 2);
+        assert_eq!(variant.materials[&1].color, "ff0000");
+        assert_eq!(variant.materials[&2].color, "0000ff");
+
+        match extract_variant(&meta, "Baz", false) {
+            Err(VariantTransferError::VariantNotExist((name, suggestions))) => {
+                assert_eq!(name, "Baz");
+                assert_eq!(suggestions, vec![vec!["Foo", "Bar"]]);
+            }
+            _ => panic!("Should not find variant"),
+        }
+    }
+
+    #[test]
+    fn test_inject_variants() {
+        let mut meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let snippet = parse_variants_json(
+            r#"{ "Night": { "Materials": { "0": {"Color": "ffffff","CompanyTint": 0,"Emission": 255,"Glassiness": 0,"Smoothness": 0,"Specular": 0} }, "Variants": null } }"#,
+        )
+        .expect("Should parse snippet");
+
+        inject_variants(&mut meta, snippet.clone(), &Some("Foo".to_string()), false)
+            .expect("Should inject");
+        let path = vec!["Foo".to_string(), "Night".to_string()];
+        assert!(get_variant_by_path(&meta, &path).is_some());
+
+        assert!(matches!(
+            inject_variants(&mut meta, snippet.clone(), &Some("Foo".to_string()), false),
+            Err(VariantTransferError::VariantAlreadyExist(_))
+        ));
+        assert!(inject_variants(&mut meta, snippet, &None, false).is_ok());
+    }
+
+    #[test]
+    fn test_inject_variants_invalid_index() {
+        let mut meta = parse_material_json(&get_test_data()).expect("Should create meta");
+        let snippet = parse_variants_json(
+            r#"{ "Night": { "Materials": { "7": {"Color": "ffffff","CompanyTint": 0,"Emission": 255,"Glassiness": 0,"Smoothness": 0,"Specular": 0} }, "Variants": null } }"#,
+        )
+        .expect("Should parse snippet");
+
+        let Err(VariantTransferError::InvalidMaterialIndexes(invalid)) =
+            inject_variants(&mut meta, snippet, &None, false)
+        else {
+            panic!("Should reject out of range index");
+        };
+        assert_eq!(invalid, vec![(vec!["Night".to_string()], 7)]);
+    }
+}
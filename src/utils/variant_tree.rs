@@ -0,0 +1,226 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    hex_to_rgb::hex_to_rgb,
+    variants::{format_variant_path, VariantNode},
+};
+
+fn overrides_label(node: &VariantNode) -> String {
+    match node.materials.len() {
+        1 => "1 override".to_string(),
+        count => format!("{} overrides", count),
+    }
+}
+
+/// Truecolor background swatch, falls back to the hex code without colors or
+/// for colors that can't be parsed.
+fn swatch(color: &str, use_color: bool) -> String {
+    match hex_to_rgb(color) {
+        Ok([r, g, b]) if use_color => format!("\x1b[48;2;{};{};{}m  \x1b[0m", r, g, b),
+        _ => format!("#{}", color.trim_start_matches('#')),
+    }
+}
+
+fn render_text_nodes(nodes: &[VariantNode], prefix: &str, use_color: bool, text: &mut String) {
+    for (position, node) in nodes.iter().enumerate() {
+        let is_last = position + 1 == nodes.len();
+        let swatches: Vec<String> = node
+            .materials
+            .values()
+            .map(|material| swatch(&material.color, use_color))
+            .collect();
+
+        text.push_str(prefix);
+        text.push_str(if is_last { "└── " } else { "├── " });
+        text.push_str(&format!("{} ({})", node.name, overrides_label(node)));
+        if !swatches.is_empty() {
+            text.push(' ');
+            text.push_str(&swatches.join(" "));
+        }
+        text.push('\n');
+
+        let child_prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+        render_text_nodes(&node.children, &child_prefix, use_color, text);
+    }
+}
+
+/// Indented tree with box drawing characters, `root` is printed on the first line.
+pub fn render_variant_tree_text(root: &str, nodes: &[VariantNode], use_color: bool) -> String {
+    let mut text = format!("{}\n", root);
+    if nodes.is_empty() {
+        text.push_str("(no variants)\n");
+    }
+    render_text_nodes(nodes, "", use_color, &mut text);
+    text
+}
+
+fn node_json(node: &VariantNode) -> Value {
+    let materials: Map<String, Value> = node
+        .materials
+        .iter()
+        .map(|(index, material)| (index.to_string(), Value::from(material.color.to_owned())))
+        .collect();
+    json!({
+        "name": node.name,
+        "path": format_variant_path(&node.path),
+        "overrides": node.materials.len(),
+        "materials": materials,
+        "children": node.children.iter().map(node_json).collect::<Vec<Value>>(),
+    })
+}
+
+pub fn variant_tree_json(nodes: &[VariantNode]) -> Value {
+    Value::Array(nodes.iter().map(node_json).collect())
+}
+
+pub fn render_variant_tree_json(nodes: &[VariantNode]) -> String {
+    serde_json::to_string_pretty(&variant_tree_json(nodes)).unwrap_or_default()
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn render_dot_nodes(nodes: &[VariantNode], parent_id: &str, dot: &mut String) {
+    for node in nodes {
+        let id = escape_dot(&format_variant_path(&node.path));
+        dot.push_str(&format!(
+            "  \"{}\" [label=\"{}\\n{}\"];\n",
+            id,
+            escape_dot(&node.name),
+            overrides_label(node)
+        ));
+        dot.push_str(&format!("  \"{}\" -> \"{}\";\n", parent_id, id));
+        render_dot_nodes(&node.children, &id, dot);
+    }
+}
+
+/// Graphviz digraph, nodes are identified by their variant path.
+pub fn render_variant_tree_dot(root: &str, nodes: &[VariantNode]) -> String {
+    // Variant paths never start with the separator so the root id can't clash
+    let root_id = "/";
+    let mut dot = String::from("digraph variants {\n  rankdir=LR;\n  node [shape=box];\n");
+    dot.push_str(&format!(
+        "  \"{}\" [label=\"{}\"];\n",
+        root_id,
+        escape_dot(root)
+    ));
+    render_dot_nodes(nodes, root_id, &mut dot);
+    dot.push_str("}\n");
+    dot
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
+
+fn render_mermaid_nodes(
+    nodes: &[VariantNode],
+    parent_id: &str,
+    next_id: &mut usize,
+    mermaid: &mut String,
+) {
+    for node in nodes {
+        let id = format!("n{}", next_id);
+        *next_id += 1;
+        mermaid.push_str(&format!(
+            "  {}[\"{}<br/>{}\"]\n",
+            id,
+            escape_mermaid(&node.name),
+            overrides_label(node)
+        ));
+        mermaid.push_str(&format!("  {} --> {}\n", parent_id, id));
+        render_mermaid_nodes(&node.children, &id, next_id, mermaid);
+    }
+}
+
+/// Mermaid flowchart, nodes are numbered in file order.
+pub fn render_variant_tree_mermaid(root: &str, nodes: &[VariantNode]) -> String {
+    let mut mermaid = String::from("flowchart LR\n");
+    mermaid.push_str(&format!("  root[\"{}\"]\n", escape_mermaid(root)));
+    render_mermaid_nodes(nodes, "root", &mut 0, &mut mermaid);
+    mermaid
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::{json_parse::parse_material_json, variants::get_variant_tree_from_meta};
+
+    use super::*;
+
+    fn get_test_nodes() -> Vec<VariantNode> {
+        let data = r#"{
+          "Materials": [],
+          "Variants": {
+            "foo": {
+              "Materials": {
+                "1": {"Color": "ff0000","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0},
+                "2": {"Color": "00ff00","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+              },
+              "Variants": {
+                "b\"ar": { "Materials": {}, "Variants": null }
+              }
+            },
+            "night": {
+              "Materials": {
+                "3": {"Color": "0000ff","CompanyTint": 0,"Emission": 0,"Glassiness": 0,"Smoothness": 0,"Specular": 0}
+              },
+              "Variants": null
+            }
+          }
+        }"#;
+        let meta = parse_material_json(data).expect("Should create meta");
+        get_variant_tree_from_meta(&meta)
+    }
+
+    #[test]
+    fn test_render_variant_tree_text() {
+        let nodes = get_test_nodes();
+
+        assert_eq!(
+            render_variant_tree_text("bus", &nodes, false),
+            "bus\n\
+             ├── foo (2 overrides) #ff0000 #00ff00\n\
+             │   └── b\"ar (0 overrides)\n\
+             └── night (1 override) #0000ff\n"
+        );
+        assert!(render_variant_tree_text("bus", &nodes, true)
+            .contains("night (1 override) \x1b[48;2;0;0;255m  \x1b[0m\n"));
+        assert_eq!(
+            render_variant_tree_text("bus", &[], false),
+            "bus\n(no variants)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_variant_tree_json() {
+        let tree: Value = serde_json::from_str(&render_variant_tree_json(&get_test_nodes()))
+            .expect("Should be JSON");
+
+        assert_eq!(tree[0]["name"], "foo");
+        assert_eq!(tree[0]["overrides"], 2);
+        assert_eq!(tree[0]["materials"]["2"], "00ff00");
+        assert_eq!(tree[0]["children"][0]["path"], "foo/b\"ar");
+        assert_eq!(tree[1]["name"], "night");
+    }
+
+    #[test]
+    fn test_render_variant_tree_dot_and_mermaid() {
+        let nodes = get_test_nodes();
+
+        let dot = render_variant_tree_dot("bus", &nodes);
+        assert!(dot.starts_with("digraph variants {\n"));
+        assert!(dot.contains("  \"foo/b\\\"ar\" [label=\"b\\\"ar\\n0 overrides\"];\n"));
+        assert!(dot.contains("  \"foo\" -> \"foo/b\\\"ar\";\n"));
+        assert!(dot.contains("  \"/\" -> \"night\";\n"));
+
+        let mermaid = render_variant_tree_mermaid("bus", &nodes);
+        assert_eq!(
+            mermaid,
+            "flowchart LR\n  root[\"bus\"]\n  \
+             n0[\"foo<br/>2 overrides\"]\n  root --> n0\n  \
+             n1[\"b#quot;ar<br/>0 overrides\"]\n  n0 --> n1\n  \
+             n2[\"night<br/>1 override\"]\n  root --> n2\n"
+        );
+    }
+}
@@ -1,114 +1,212 @@
-use super::json_parse::{VTMetaSchema, VariantSchema, Variants};
+use indexmap::IndexMap;
+use strsim::levenshtein;
 
-fn get_variants_names_from_variant(variant: &VariantSchema) -> Option<Vec<String>> {
-    let mut variant_names: Vec<String> = Vec::new();
+use super::diagnostic::JsonPath;
+use super::json_parse::{MaterialSchema, VTMetaSchema, VariantSchema, Variants};
 
-    if let Some(variants) = &variant.variants {
-        for (key, variant) in variants {
-            variant_names.push(key.to_owned());
-            let nested_names = get_variants_names_from_variant(variant);
-            if let Some(nested_keys) = nested_names {
-                variant_names.extend(nested_keys);
-            }
-        }
-        return Some(variant_names);
-    }
-
-    None
+/// Variant with its sub-variants, children keep the order of the file.
+#[derive(Debug, Clone)]
+pub struct VariantNode {
+    pub name: String,
+    pub path: VariantPath,
+    pub materials: IndexMap<u8, MaterialSchema>,
+    pub children: Vec<VariantNode>,
 }
 
-pub fn get_variants_names_from_meta(meta: &VTMetaSchema) -> Option<Vec<String>> {
-    let mut variant_names: Vec<String> = Vec::new();
-    if let Some(variants) = &meta.variants {
-        for (key, variant) in variants {
-            variant_names.push(key.to_owned());
-            let nested_names = get_variants_names_from_variant(variant);
-            if let Some(nested_keys) = nested_names {
-                variant_names.extend(nested_keys);
-            }
-        }
-        if !variant_names.is_empty() {
-            return Some(variant_names);
-        }
+fn build_variant_nodes(variants: &Variants, parent: &VariantPath) -> Vec<VariantNode> {
+    let mut nodes: Vec<VariantNode> = Vec::new();
+    for (key, variant) in variants {
+        let mut path = parent.clone();
+        path.push(key.to_owned());
+        let children = match &variant.variants {
+            Some(sub_variants) => build_variant_nodes(sub_variants, &path),
+            None => Vec::new(),
+        };
+        nodes.push(VariantNode {
+            name: key.to_owned(),
+            path,
+            materials: variant.materials.clone(),
+            children,
+        });
     }
+    nodes
+}
 
-    None
+pub fn get_variant_tree_from_meta(meta: &VTMetaSchema) -> Vec<VariantNode> {
+    match &meta.variants {
+        Some(variants) => build_variant_nodes(variants, &Vec::new()),
+        None => Vec::new(),
+    }
 }
 
 pub type VariantPath = Vec<String>;
 
-fn search_for_variant_path(variants: &Variants, variant_name: &String) -> Option<VariantPath> {
-    if let Some(_) = variants.get(variant_name) {
-        return Some(vec![variant_name.to_owned()]);
-    }
+pub const VARIANT_PATH_SEPARATOR: char = '/';
 
-    for (key, sub_variant) in variants {
-        if let Some(found) = get_variant_subpath_from_variant(&sub_variant, &variant_name) {
-            let mut result = vec![key.to_string()];
-            result.extend(found);
-            return Some(result);
-        }
+/// JSON path of the variant in the .meta file, like `Variants.foo.Variants.bar`.
+pub fn variant_json_path(variant_path: &VariantPath) -> JsonPath {
+    let mut path: JsonPath = Vec::new();
+    for name in variant_path {
+        path.push("Variants".to_string());
+        path.push(name.to_owned());
     }
-
-    None
+    path
 }
 
-fn get_variant_subpath_from_variant(
-    variant: &VariantSchema,
-    variant_name: &String,
-) -> Option<VariantPath> {
-    variant
-        .variants
-        .as_ref()
-        .and_then(|variants| search_for_variant_path(variants, variant_name))
+#[derive(Debug)]
+pub enum VariantLookupError {
+    VariantNotExist,
+    AmbiguousVariant(Vec<VariantPath>),
 }
 
+/// Maximum number of variants suggested for a mistyped name
+pub const VARIANT_SUGGESTIONS: usize = 5;
+
+/// Resolves a variant given either by bare name (`deep`) or by explicit path
+/// (`foo/bar/deep`). A bare name used by more than one variant in the tree is
+/// reported as ambiguous together with all matching paths.
 pub fn get_variant_path_from_meta(
     meta: &VTMetaSchema,
-    variant_name: &String,
-) -> Option<VariantPath> {
-    meta.variants
-        .as_ref()
-        .and_then(|variants| search_for_variant_path(variants, variant_name))
+    variant_name: &str,
+) -> Result<VariantPath, VariantLookupError> {
+    if variant_name.contains(VARIANT_PATH_SEPARATOR) {
+        let variant_path = parse_variant_path(variant_name);
+        return match get_variant_by_path(meta, &variant_path) {
+            Some(_) => Ok(variant_path),
+            None => Err(VariantLookupError::VariantNotExist),
+        };
+    }
+
+    let matches: Vec<VariantPath> = get_variant_paths_from_meta(meta)
+        .into_iter()
+        .filter(|path| path.last().is_some_and(|name| name == variant_name))
+        .collect();
+    single_match(matches)
 }
 
-fn search_for_variant<'a>(
-    variants: &'a Variants,
-    variant_name: &'a String,
-) -> Option<&'a VariantSchema> {
-    if let Some(variant) = variants.get(variant_name) {
-        return Some(variant);
+/// Like `get_variant_path_from_meta`, with `ignore_case` names differing only
+/// in letter case are accepted when there is no exact match.
+pub fn find_variant_path(
+    meta: &VTMetaSchema,
+    variant_name: &str,
+    ignore_case: bool,
+) -> Result<VariantPath, VariantLookupError> {
+    match get_variant_path_from_meta(meta, variant_name) {
+        Err(VariantLookupError::VariantNotExist) if ignore_case => {}
+        result => return result,
     }
 
-    for (_, sub_variant) in variants {
-        if let Some(found) = get_variant_from_variant(&sub_variant, &variant_name) {
-            return Some(found);
-        }
+    let wanted: VariantPath = parse_variant_path(&variant_name.to_lowercase());
+    let explicit = variant_name.contains(VARIANT_PATH_SEPARATOR);
+    let matches: Vec<VariantPath> = get_variant_paths_from_meta(meta)
+        .into_iter()
+        .filter(|path| {
+            let lowercase: VariantPath = path.iter().map(|name| name.to_lowercase()).collect();
+            match explicit {
+                true => lowercase == wanted,
+                false => lowercase.last() == wanted.last(),
+            }
+        })
+        .collect();
+    single_match(matches)
+}
+
+fn parse_variant_path(variant_name: &str) -> VariantPath {
+    variant_name
+        .split(VARIANT_PATH_SEPARATOR)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| segment.to_string())
+        .collect()
+}
+
+fn single_match(mut matches: Vec<VariantPath>) -> Result<VariantPath, VariantLookupError> {
+    match matches.len() {
+        0 => Err(VariantLookupError::VariantNotExist),
+        1 => Ok(matches.remove(0)),
+        _ => Err(VariantLookupError::AmbiguousVariant(matches)),
     }
+}
 
-    None
+/// Variants with a name or path close to the mistyped `variant_name`, closest
+/// first. Letter case is ignored and a name containing the input counts as
+/// close, so `nigh` also suggests `night_red`.
+pub fn suggest_variant_paths(meta: &VTMetaSchema, variant_name: &str) -> Vec<VariantPath> {
+    let wanted = variant_name.to_lowercase();
+    let max_distance = (wanted.chars().count() / 3).max(2);
+
+    let mut ranked: Vec<(usize, VariantPath)> = get_variant_paths_from_meta(meta)
+        .into_iter()
+        .filter_map(|path| {
+            // Paths are compared as a whole, bare names to the last segment
+            let candidate = match wanted.contains(VARIANT_PATH_SEPARATOR) {
+                true => format_variant_path(&path).to_lowercase(),
+                false => path.last()?.to_lowercase(),
+            };
+            let mut distance = levenshtein(&wanted, &candidate);
+            if candidate.contains(&wanted) {
+                distance = distance.min(1);
+            }
+            (distance <= max_distance).then_some((distance, path))
+        })
+        .collect();
+    // Stable sort keeps file order for equally close variants
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked
+        .into_iter()
+        .take(VARIANT_SUGGESTIONS)
+        .map(|(_, path)| path)
+        .collect()
 }
 
-fn get_variant_from_variant<'a>(
-    variant: &'a VariantSchema,
-    variant_name: &'a String,
-) -> Option<&'a VariantSchema> {
-    if let Some(meta_variants) = &variant.variants {
-        return search_for_variant(meta_variants, variant_name);
+pub fn format_variant_path(variant_path: &VariantPath) -> String {
+    variant_path.join(&VARIANT_PATH_SEPARATOR.to_string())
+}
+
+fn collect_variant_paths(variants: &Variants, parent: &VariantPath, paths: &mut Vec<VariantPath>) {
+    for (key, variant) in variants {
+        let mut path = parent.clone();
+        path.push(key.to_owned());
+        paths.push(path.clone());
+        if let Some(sub_variants) = &variant.variants {
+            collect_variant_paths(sub_variants, &path, paths);
+        }
     }
+}
 
-    None
+pub fn get_variant_paths_from_meta(meta: &VTMetaSchema) -> Vec<VariantPath> {
+    let mut paths: Vec<VariantPath> = Vec::new();
+    if let Some(variants) = &meta.variants {
+        collect_variant_paths(variants, &Vec::new(), &mut paths);
+    }
+    paths
 }
 
-pub fn get_variant_from_meta<'a>(
+pub fn get_variant_by_path<'a>(
     meta: &'a VTMetaSchema,
-    variant_name: &'a String,
+    variant_path: &VariantPath,
 ) -> Option<&'a VariantSchema> {
-    if let Some(meta_variants) = &meta.variants {
-        return search_for_variant(meta_variants, variant_name);
+    let mut variants = meta.variants.as_ref();
+    let mut found: Option<&VariantSchema> = None;
+
+    for variant_name in variant_path {
+        let variant = variants?.get(variant_name)?;
+        variants = variant.variants.as_ref();
+        found = Some(variant);
     }
 
-    None
+    found
+}
+
+pub fn get_variant_by_path_mut<'a>(
+    meta: &'a mut VTMetaSchema,
+    variant_path: &VariantPath,
+) -> Option<&'a mut VariantSchema> {
+    let (first, rest) = variant_path.split_first()?;
+    let mut variant = meta.variants.as_mut()?.get_mut(first)?;
+    for variant_name in rest {
+        variant = variant.variants.as_mut()?.get_mut(variant_name)?;
+    }
+    Some(variant)
 }
 
 #[cfg(test)]
@@ -203,25 +301,23 @@ mod tests {
     }
 
     #[test]
-    fn test_get_variants_names() {
+    fn test_get_variant_tree_from_meta() {
         let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
-        let mut variants = get_variants_names_from_meta(&meta).expect("Should get variants");
-        variants.sort();
+        let tree = get_variant_tree_from_meta(&meta);
 
-        let mut expected_variants = vec![
-            "foo".to_string(),
-            "bar".to_string(),
-            "night".to_string(),
-            "lorem".to_string(),
-            "ipsum".to_string(),
-            "deep".to_string(),
-        ];
-        expected_variants.sort();
-        assert_eq!(variants, expected_variants);
+        let names: Vec<&str> = tree.iter().map(|node| node.name.as_str()).collect();
+        assert_eq!(names, vec!["foo", "night"]);
+        assert_eq!(tree[0].materials.keys().collect::<Vec<&u8>>(), vec![&12]);
+        assert_eq!(tree[1].materials.len(), 2);
+        assert!(tree[1].children.is_empty());
+
+        let bar = &tree[0].children[0];
+        assert_eq!(bar.path, vec!["foo", "bar"]);
+        assert_eq!(bar.children[0].children[0].children[0].name, "deep");
 
         let meta_without =
             parse_material_json(&get_test_data_without_variants()).expect("Should create meta");
-        assert_eq!(get_variants_names_from_meta(&meta_without), None);
+        assert!(get_variant_tree_from_meta(&meta_without).is_empty());
     }
 
     #[test]
@@ -235,8 +331,7 @@ mod tests {
             "deep".to_string(),
         ];
 
-        let variants =
-            get_variant_path_from_meta(&meta, &"deep".to_string()).expect("Should get variants");
+        let variants = get_variant_path_from_meta(&meta, "deep").expect("Should get variants");
 
         assert_eq!(variants, expected_variants);
     }
@@ -246,9 +341,144 @@ mod tests {
         let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
         let expected_variants = vec!["night".to_string()];
 
-        let variants =
-            get_variant_path_from_meta(&meta, &"night".to_string()).expect("Should get variants");
+        let variants = get_variant_path_from_meta(&meta, "night").expect("Should get variants");
 
         assert_eq!(variants, expected_variants);
     }
+
+    #[test]
+    fn test_get_variant_path_from_meta_explicit_path() {
+        let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+
+        let variants = get_variant_path_from_meta(&meta, "foo/bar/lorem").expect("Should get path");
+        assert_eq!(variants, vec!["foo", "bar", "lorem"]);
+
+        assert!(matches!(
+            get_variant_path_from_meta(&meta, "foo/lorem"),
+            Err(VariantLookupError::VariantNotExist)
+        ));
+        assert!(matches!(
+            get_variant_path_from_meta(&meta, "unknown"),
+            Err(VariantLookupError::VariantNotExist)
+        ));
+    }
+
+    #[test]
+    fn test_get_variant_path_from_meta_ambiguous() {
+        let data = r#"{
+          "Materials": [],
+          "Variants": {
+            "foo": { "Materials": {}, "Variants": { "night": { "Materials": {}, "Variants": null } } },
+            "bar": { "Materials": {}, "Variants": { "night": { "Materials": {}, "Variants": null } } }
+          }
+        }"#;
+        let meta = parse_material_json(data).expect("Should create meta");
+
+        let Err(VariantLookupError::AmbiguousVariant(paths)) =
+            get_variant_path_from_meta(&meta, "night")
+        else {
+            panic!("Should report ambiguous variant");
+        };
+        assert_eq!(paths, vec![vec!["foo", "night"], vec!["bar", "night"]]);
+
+        let variants = get_variant_path_from_meta(&meta, "bar/night").expect("Should get path");
+        assert_eq!(variants, vec!["bar", "night"]);
+    }
+
+    #[test]
+    fn test_find_variant_path_ignore_case() {
+        let data = r#"{
+          "Materials": [],
+          "Variants": {
+            "Night": { "Materials": {}, "Variants": { "Red": { "Materials": {}, "Variants": null } } },
+            "night": { "Materials": {}, "Variants": null },
+            "Day": { "Materials": {}, "Variants": null }
+          }
+        }"#;
+        let meta = parse_material_json(data).expect("Should create meta");
+
+        assert!(matches!(
+            find_variant_path(&meta, "DAY", false),
+            Err(VariantLookupError::VariantNotExist)
+        ));
+        assert_eq!(
+            find_variant_path(&meta, "DAY", true).expect("Should get path"),
+            vec!["Day"]
+        );
+        assert_eq!(
+            find_variant_path(&meta, "night/RED", true).expect("Should get path"),
+            vec!["Night", "Red"]
+        );
+        // Exact match wins over case-insensitive ones
+        assert_eq!(
+            find_variant_path(&meta, "night", true).expect("Should get path"),
+            vec!["night"]
+        );
+        assert!(matches!(
+            find_variant_path(&meta, "NIGHT", true),
+            Err(VariantLookupError::AmbiguousVariant(paths)) if paths.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_suggest_variant_paths() {
+        let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+
+        assert_eq!(
+            suggest_variant_paths(&meta, "Deap"),
+            vec![vec!["foo", "bar", "lorem", "ipsum", "deep"]]
+        );
+        assert_eq!(
+            suggest_variant_paths(&meta, "foo/bar/lorme")[0],
+            vec!["foo", "bar", "lorem"]
+        );
+        assert_eq!(suggest_variant_paths(&meta, "NIGH")[0], vec!["night"]);
+        assert!(suggest_variant_paths(&meta, "completely-different").is_empty());
+    }
+
+    #[test]
+    fn test_get_variant_paths_from_meta() {
+        let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+        let mut paths = get_variant_paths_from_meta(&meta);
+        paths.sort();
+
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths[0], vec!["foo".to_string()]);
+        assert_eq!(
+            paths[4],
+            vec![
+                "foo".to_string(),
+                "bar".to_string(),
+                "lorem".to_string(),
+                "ipsum".to_string(),
+                "deep".to_string(),
+            ]
+        );
+        assert_eq!(paths[5], vec!["night".to_string()]);
+
+        let meta_without =
+            parse_material_json(&get_test_data_without_variants()).expect("Should create meta");
+        assert!(get_variant_paths_from_meta(&meta_without).is_empty());
+    }
+
+    #[test]
+    fn test_variant_json_path() {
+        let path = vec!["foo".to_string(), "bar".to_string()];
+        assert_eq!(
+            variant_json_path(&path),
+            vec!["Variants", "foo", "Variants", "bar"]
+        );
+        assert!(variant_json_path(&Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_get_variant_by_path() {
+        let meta = parse_material_json(&get_test_data_with_variants()).expect("Should create meta");
+        let path = vec!["foo".to_string(), "bar".to_string()];
+        let variant = get_variant_by_path(&meta, &path).expect("Should get variant");
+        assert!(variant.materials.contains_key(&15));
+
+        let missing = vec!["bar".to_string()];
+        assert!(get_variant_by_path(&meta, &missing).is_none());
+    }
 }
@@ -0,0 +1,133 @@
+use notify::{event::ModifyKind, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+/// Time without changes after which collected changes are handled
+pub const WATCH_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Changes of files in a watched directory. Keep it alive as long as changes
+/// are needed, dropping it stops watching.
+pub struct DirectoryWatcher {
+    _watcher: RecommendedWatcher,
+    receiver: Receiver<PathBuf>,
+}
+
+/// True for events changing file content. Reads and metadata changes are
+/// skipped, otherwise reading a file for an export would trigger another export.
+fn is_content_change(kind: &EventKind) -> bool {
+    match kind {
+        EventKind::Create(_) | EventKind::Remove(_) => true,
+        EventKind::Modify(ModifyKind::Metadata(_)) => false,
+        EventKind::Modify(_) => true,
+        _ => false,
+    }
+}
+
+impl DirectoryWatcher {
+    /// Watches `directory`, and its subdirectories when `recursive`. Changed
+    /// paths are relative to `directory`.
+    pub fn new(directory: &Path, recursive: bool) -> Result<Self, notify::Error> {
+        // Events carry absolute paths, relative paths are resolved against it
+        let root = fs::canonicalize(directory)?;
+        let (sender, receiver) = channel();
+        let event_root = root.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            if !is_content_change(&event.kind) {
+                return;
+            }
+            for path in event.paths {
+                if let Ok(relative_path) = path.strip_prefix(&event_root) {
+                    let _ = sender.send(relative_path.to_path_buf());
+                }
+            }
+        })?;
+        let mode = match recursive {
+            true => RecursiveMode::Recursive,
+            false => RecursiveMode::NonRecursive,
+        };
+        watcher.watch(&root, mode)?;
+        Ok(DirectoryWatcher {
+            _watcher: watcher,
+            receiver,
+        })
+    }
+
+    /// Waits for changes, see [`next_batch`].
+    pub fn next_batch(&self, debounce: Duration) -> Option<Vec<PathBuf>> {
+        next_batch(&self.receiver, debounce)
+    }
+}
+
+/// Blocks until a path arrives, then collects paths until none arrives for
+/// `debounce`, so a burst of saves is handled once. Paths are listed once in
+/// the order of their first change. `None` when nothing can arrive anymore.
+pub fn next_batch(receiver: &Receiver<PathBuf>, debounce: Duration) -> Option<Vec<PathBuf>> {
+    let first = receiver.recv().ok()?;
+    Some(collect_batch(first, || receiver.recv_timeout(debounce)))
+}
+
+/// Collects paths following `first` until `next` times out or disconnects.
+fn collect_batch(
+    first: PathBuf,
+    mut next: impl FnMut() -> Result<PathBuf, RecvTimeoutError>,
+) -> Vec<PathBuf> {
+    let mut paths = vec![first];
+    while let Ok(path) = next() {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_batch_groups_until_timeout() {
+        let mut events = vec![
+            Ok(PathBuf::from("b.obj.meta")),
+            Ok(PathBuf::from("a.obj.meta")),
+            Err(RecvTimeoutError::Timeout),
+            Ok(PathBuf::from("c.obj.meta")),
+        ]
+        .into_iter();
+        let mut next = || events.next().unwrap_or(Err(RecvTimeoutError::Disconnected));
+
+        let first = collect_batch(PathBuf::from("a.obj.meta"), &mut next);
+        assert_eq!(
+            first,
+            vec![PathBuf::from("a.obj.meta"), PathBuf::from("b.obj.meta")]
+        );
+        let second = next().expect("Should get next change");
+        assert_eq!(
+            collect_batch(second, &mut next),
+            vec![PathBuf::from("c.obj.meta")]
+        );
+    }
+
+    #[test]
+    fn test_next_batch_ends_on_closed_channel() {
+        let (sender, receiver) = channel();
+        for name in ["a.obj.meta", "b.obj.meta", "a.obj.meta"] {
+            sender.send(PathBuf::from(name)).expect("Should send");
+        }
+        drop(sender);
+
+        // The closed channel ends the batch without waiting for the debounce
+        let batch = next_batch(&receiver, Duration::from_secs(3600)).expect("Should get batch");
+        assert_eq!(
+            batch,
+            vec![PathBuf::from("a.obj.meta"), PathBuf::from("b.obj.meta")]
+        );
+        assert!(next_batch(&receiver, Duration::from_secs(3600)).is_none());
+    }
+}
@@ -0,0 +1,63 @@
+use std::{fs, path::Path};
+
+use serde::Serialize;
+
+#[derive(Debug)]
+pub enum WriteMetaError {
+    SerializeError,
+    WriteError,
+}
+
+/// Serializes to the same layout as the Voxel Tycoon asset editor: two space
+/// indentation, no trailing newline.
+pub fn serialize_meta_json<T: Serialize>(value: &T) -> Result<String, WriteMetaError> {
+    serde_json::to_string_pretty(value).map_err(|_| WriteMetaError::SerializeError)
+}
+
+pub fn write_meta_file<T: Serialize>(path: &Path, value: &T) -> Result<(), WriteMetaError> {
+    let content = serialize_meta_json(value)?;
+    fs::write(path, content).map_err(|_| WriteMetaError::WriteError)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::json_parse::parse_material_json;
+
+    use super::*;
+
+    #[test]
+    fn test_serialize_meta_json_round_trip() {
+        let data = r#"{
+  "Materials": [
+    {
+      "Color": "424242",
+      "CompanyTint": 21,
+      "Emission": 37,
+      "Glassiness": 69,
+      "Smoothness": 66,
+      "Specular": 13
+    }
+  ],
+  "Variants": {
+    "night": {
+      "Materials": {
+        "0": {
+          "Color": "ffffff",
+          "CompanyTint": 0,
+          "Emission": 255,
+          "Glassiness": 0,
+          "Smoothness": 0,
+          "Specular": 0
+        }
+      },
+      "Variants": null
+    }
+  }
+}"#;
+
+        let meta = parse_material_json(data).expect("Should create meta");
+        let serialized = serialize_meta_json(&meta).expect("Should serialize meta");
+
+        assert_eq!(serialized, data);
+    }
+}
@@ -1,44 +1,125 @@
 mod commands;
+mod error;
 mod utils;
 
-use clap::{Parser, Subcommand};
-use commands::export::{ExportArgs, ExportError};
+use clap::{Parser, Subcommand, ValueEnum};
+use commands::config::ConfigArgs;
+use commands::dedupe::DedupeArgs;
+use commands::edit::EditArgs;
+use commands::explain::ExplainArgs;
+use commands::export::ExportArgs;
+use commands::inspect::InspectArgs;
+use commands::lint::LintArgs;
+use commands::schema::SchemaArgs;
+use commands::validate::ValidateArgs;
+use commands::variant::VariantArgs;
+use commands::variants::VariantsArgs;
+use error::CliError;
+use std::process::ExitCode;
+use utils::output::{Output, OutputFormat};
 
 #[derive(Parser)]
 #[command(version, about = "CLI tools for Voxel Tycoon mod makers", long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Print results as text or as a single JSON document
+    #[arg(long, global = true, value_enum, default_value_t = OutputArg::Text)]
+    output: OutputArg,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputArg {
+    Text,
+    Json,
+}
+
+impl From<OutputArg> for OutputFormat {
+    fn from(arg: OutputArg) -> Self {
+        match arg {
+            OutputArg::Text => OutputFormat::Text,
+            OutputArg::Json => OutputFormat::Json,
+        }
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Export(ExportArgs),
+    Dedupe(DedupeArgs),
+    Variant(VariantArgs),
+    Variants(VariantsArgs),
+    Inspect(InspectArgs),
+    Explain(ExplainArgs),
+    Edit(EditArgs),
+    Validate(ValidateArgs),
+    Lint(LintArgs),
+    Schema(SchemaArgs),
+    Config(ConfigArgs),
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Cli::parse();
 
-    match args.command {
-        Commands::Export(args) => run_export(args),
-    }
-}
+    let mut output = Output::new(args.output.into());
 
-fn run_export(args: ExportArgs) {
-    match commands::export::run(args) {
-        Err(ExportError::FileRead) => println!("Failed to read the file"),
-        Err(ExportError::NoOperations) => println!(
-            "Specify export operation. Use -h for help or if you want export all textures use -a"
+    let (command, result): (&str, Result<(), CliError>) = match args.command {
+        Commands::Export(args) => (
+            "export",
+            commands::export::run(args, &mut output).map_err(CliError::from),
         ),
-        Err(ExportError::JsonParse) => {
-            println!("Invalid input file. Verify if you provided .meta file.",)
-        }
-        Err(ExportError::InvalidVariantName((variant, available_variants))) => {
-            println!("Invalid variant name: {}", variant);
-            if let Some(variants_list) = available_variants {
-                println!("Available variants: {}", variants_list.join(", "));
-            }
-        }
-        Ok(_) => {}
+        Commands::Dedupe(args) => (
+            "dedupe",
+            commands::dedupe::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Variant(args) => (
+            "variant",
+            commands::variant::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Variants(args) => (
+            "variants",
+            commands::variants::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Inspect(args) => (
+            "inspect",
+            commands::inspect::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Explain(args) => (
+            "explain",
+            commands::explain::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Edit(args) => (
+            "edit",
+            commands::edit::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Validate(args) => (
+            "validate",
+            commands::validate::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Lint(args) => (
+            "lint",
+            commands::lint::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Schema(args) => (
+            "schema",
+            commands::schema::run(args, &mut output).map_err(CliError::from),
+        ),
+        Commands::Config(args) => (
+            "config",
+            commands::config::run(args, &mut output).map_err(CliError::from),
+        ),
+    };
+
+    let exit_code = match &result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => ExitCode::from(e),
+    };
+    if output.is_json() {
+        let failure = result.err().map(|e| (e.exit_code(), e.to_string()));
+        output.print_json(command, failure);
+    } else if let Err(e) = result {
+        eprintln!("{}", e);
     }
+    exit_code
 }